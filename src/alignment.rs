@@ -0,0 +1,206 @@
+use crate::token::TokenKind;
+
+/// Where lines are placed within the `max_width`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Alignment {
+    /// Lines start at offset 0.
+    #[default]
+    Left,
+
+    /// Lines end at the `max_width`.
+    Right,
+
+    /// Lines are centered within the `max_width`.
+    Center,
+
+    /// Wrapped lines are stretched to the `max_width` by widening their whitespace.
+    ///
+    /// The last line of a paragraph, lines that end with a newline, and lines that break a word
+    /// wider than the line are placed at offset 0.
+    Justify,
+}
+
+impl Alignment {
+    /// The offset of the start of a line of `tokens` and the extra width of each of its
+    /// `Optional` tokens.
+    ///
+    /// `is_wrapped` is true if the line was wrapped at a break opportunity. Trailing whitespace
+    /// is not included in the width of the line.
+    pub(crate) fn align<'a, T>(self, tokens: T, max_width: u32, is_wrapped: bool) -> (u32, Vec<u32>)
+    where
+        T: Iterator<Item = &'a TokenKind>,
+    {
+        let tokens: Vec<&TokenKind> = tokens.filter(|token| !token.is_newline()).collect();
+        let trailing = tokens
+            .iter()
+            .rev()
+            .take_while(|token| token.is_optional())
+            .count();
+        let tokens = &tokens[..tokens.len() - trailing];
+
+        let width: u32 = tokens.iter().map(|token| token.width()).sum();
+        let slack = max_width.saturating_sub(width);
+
+        match self {
+            Alignment::Left => (0, Vec::new()),
+            Alignment::Right => (slack, Vec::new()),
+            Alignment::Center => (slack / 2, Vec::new()),
+            Alignment::Justify if is_wrapped => {
+                let spaces = tokens.iter().filter(|token| token.is_optional()).count() as u32;
+
+                // The remainder is spread over the first spaces
+                let extra = (0..spaces)
+                    .map(|space| slack / spaces + u32::from(space < slack % spaces))
+                    .collect();
+                (0, extra)
+            }
+            Alignment::Justify => (0, Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{
+        CharPosition, Measure, Position, TTFParserMeasure, WhiteSpaceWordWrap, WrapWithPosition,
+    };
+
+    use super::*;
+
+    fn known(positions: &[CharPosition]) -> Vec<Position> {
+        positions
+            .iter()
+            .filter_map(|position| match position {
+                CharPosition::Known(position) => Some(*position),
+                CharPosition::Unknown(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn right() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(10000, &measure).with_alignment(Alignment::Right);
+        let positions: Vec<CharPosition> = "12 3  \n4".wrap_with_position(&word_wrap).collect();
+        let positions = known(&positions);
+
+        // Trailing whitespace is not part of the line
+        let width = measure.str("12") + measure.str(" ") + measure.str("3");
+        assert_eq!(positions[0].offset, 10000 - width);
+        assert_eq!(positions[3].offset + u32::from(positions[3].width), 10000);
+
+        let last = positions.last().unwrap();
+        assert_eq!(last.line, 1);
+        assert_eq!(last.offset, 10000 - measure.str("4"));
+    }
+
+    #[test]
+    fn right_line_widths() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(10000, &measure)
+            .with_line_widths(5000, |line| (line == 0).then_some(5000))
+            .with_alignment(Alignment::Right);
+        let positions: Vec<CharPosition> = "12 34".wrap_with_position(&word_wrap).collect();
+        let positions = known(&positions);
+
+        // Each line ends at its own width
+        assert_eq!(positions[0].offset, 5000 - measure.str("12"));
+        assert_eq!(positions[2].line, 1);
+        assert_eq!(positions[2].offset, 10000 - measure.str("34"));
+    }
+
+    #[test]
+    fn center() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(10000, &measure).with_alignment(Alignment::Center);
+        let positions: Vec<CharPosition> = "12".wrap_with_position(&word_wrap).collect();
+        let positions = known(&positions);
+
+        assert_eq!(positions[0].offset, (10000 - measure.str("12")) / 2);
+    }
+
+    #[test]
+    fn justify() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(10000, &measure).with_alignment(Alignment::Justify);
+        let text = "aa bb cc dd ee\nff gg";
+        let positions: Vec<CharPosition> = text.wrap_with_position(&word_wrap).collect();
+        let positions = known(&positions);
+
+        // The wrapped line is stretched to the max_width
+        let first_line: Vec<&Position> = positions.iter().filter(|p| p.line == 0).collect();
+        let last = first_line.last().unwrap();
+        assert_eq!(first_line[0].offset, 0);
+        assert_eq!(last.offset + u32::from(last.width), 10000);
+
+        // The extra space is spread evenly over the whitespace
+        let gaps: Vec<u32> = first_line
+            .windows(2)
+            .filter(|pair| pair[1].ch == ' ')
+            .map(|pair| pair[1].offset - (pair[0].offset + u32::from(pair[0].width)))
+            .collect();
+        assert!(gaps.iter().all(|&gap| gap == 0));
+        let stretched: Vec<u32> = first_line
+            .windows(2)
+            .filter(|pair| pair[0].ch == ' ')
+            .map(|pair| pair[1].offset - (pair[0].offset + u32::from(pair[0].width)))
+            .collect();
+        assert!(stretched.iter().max().unwrap() - stretched.iter().min().unwrap() <= 1);
+
+        // Lines that end with a newline, or the text, are not stretched
+        for line in 1..=2 {
+            let line: Vec<&Position> = positions.iter().filter(|p| p.line == line).collect();
+            assert_eq!(line[0].offset, 0);
+            assert!(line
+                .windows(2)
+                .all(|pair| { pair[1].offset == pair[0].offset + u32::from(pair[0].width) }));
+        }
+    }
+
+    #[test]
+    fn no_whitespace() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(5000, &measure).with_alignment(Alignment::Justify);
+        let positions: Vec<CharPosition> = "1234567890".wrap_with_position(&word_wrap).collect();
+        let positions = known(&positions);
+
+        // A line without whitespace can not be stretched
+        assert_eq!(positions[0].offset, 0);
+        assert_eq!(positions[4].line, 1);
+    }
+    #[test]
+    fn forced() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(10000, &measure).with_alignment(Alignment::Justify);
+        let text = "aa bb 1234567890123";
+        let positions: Vec<CharPosition> = text.wrap_with_position(&word_wrap).collect();
+        let positions = known(&positions);
+
+        // A line that breaks a word wider than the line is not stretched
+        let first_line: Vec<&Position> = positions.iter().filter(|p| p.line == 0).collect();
+        assert_eq!(first_line.last().unwrap().ch, '3');
+        assert!(first_line
+            .windows(2)
+            .all(|pair| { pair[1].offset == pair[0].offset + u32::from(pair[0].width) }));
+    }
+}
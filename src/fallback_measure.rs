@@ -0,0 +1,196 @@
+use ttf_parser::{Face, GlyphId};
+
+use crate::{measure::is_kerned, Measure, TTFParserMeasure};
+
+/// Measures each char with the first face that has a glyph for it, e.g. a CJK font after a Latin
+/// one.
+///
+/// Widths are in the units of the first face, the widths of faces with different units per em are
+/// scaled to match. Each `Position` has the index of the face that measured it.
+#[derive(Clone, Debug)]
+pub struct FallbackMeasure<'a> {
+    measures: Vec<TTFParserMeasure<'a>>,
+
+    /// Converts the units of each face to the units of the first
+    scales: Vec<f32>,
+}
+
+impl<'a> FallbackMeasure<'a> {
+    /// Creates a new `FallbackMeasure` that tries `faces` in order.
+    pub fn new(faces: &[&'a Face<'a>]) -> Self {
+        let units_per_em = faces
+            .first()
+            .map_or(1.0, |face| f32::from(face.units_per_em()));
+
+        Self {
+            measures: faces
+                .iter()
+                .map(|face| TTFParserMeasure::new(face))
+                .collect(),
+            scales: faces
+                .iter()
+                .map(|face| units_per_em / f32::from(face.units_per_em()))
+                .collect(),
+        }
+    }
+
+    /// The index of the first face that has a glyph for `c`.
+    pub fn face(&self, c: char) -> Option<usize> {
+        self.lookup(c).map(|(face, _)| face)
+    }
+
+    /// The first face that has a glyph for `c`, with the width of `c` in the units of the first
+    /// face.
+    fn lookup(&self, c: char) -> Option<(usize, u16)> {
+        self.measures
+            .iter()
+            .enumerate()
+            .find_map(|(face, measure)| {
+                let width = measure.char(c)?;
+                Some((face, (f32::from(width) * self.scales[face]).round() as u16))
+            })
+    }
+
+    /// The kerning between `left` and `right` in `face`, in the units of the first face.
+    fn face_kerning(&self, face: usize, left: char, right: char) -> i16 {
+        let kerning = self.measures[face].kerning(left, right);
+        (f32::from(kerning) * self.scales[face]).round() as i16
+    }
+}
+
+impl<'a> Measure for FallbackMeasure<'a> {
+    fn str(&self, text: &str) -> u32 {
+        let mut previous: Option<(char, Option<usize>)> = None;
+
+        text.chars().fold(0, |width: u32, c| {
+            let lookup = self.lookup(c);
+            let face = lookup.map(|(face, _)| face);
+
+            // There is no kerning between faces
+            let kerning = match (previous.replace((c, face)), face) {
+                (Some((left, Some(left_face))), Some(face))
+                    if left_face == face && is_kerned(left, c) =>
+                {
+                    self.face_kerning(face, left, c)
+                }
+                _ => 0,
+            };
+            width
+                .saturating_add(u32::from(lookup.map_or(0, |(_, width)| width)))
+                .saturating_add_signed(i32::from(kerning))
+        })
+    }
+
+    fn char(&self, c: char) -> Option<u16> {
+        self.lookup(c).map(|(_, width)| width)
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        // There is no kerning between faces
+        match (self.face(left), self.face(right)) {
+            (Some(left_face), Some(right_face)) if left_face == right_face => {
+                self.face_kerning(left_face, left, right)
+            }
+            _ => 0,
+        }
+    }
+
+    fn face_at(&self, _index: usize, c: char) -> Option<usize> {
+        self.face(c)
+    }
+
+    fn glyph(&self, c: char) -> Option<GlyphId> {
+        self.measures[self.face(c)?].glyph(c)
+    }
+
+    fn char_glyph_at(&self, _index: usize, c: char) -> (Option<u16>, Option<GlyphId>) {
+        match self.lookup(c) {
+            Some((face, width)) => (Some(width), self.measures[face].glyph(c)),
+            None => (None, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CharPosition, Position, UnknownChar, WhiteSpaceWordWrap, WrapWithPosition};
+
+    use super::*;
+
+    #[test]
+    fn fallback() {
+        let demo_data = crate::tests::read_demo_font();
+        let demo_face = Face::parse(&demo_data, 0).expect("TTF should be valid");
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+
+        // The demo font only has an 'A', in units of 1000 per em
+        let measure = FallbackMeasure::new(&[&demo_face, &font_face]);
+        assert_eq!(measure.face('A'), Some(0));
+        assert_eq!(measure.face('B'), Some(1));
+        assert_eq!(measure.face('今'), None);
+
+        // Roboto's 'B' is 1275 of 2048 units per em
+        assert_eq!(measure.char('A'), Some(540));
+        assert_eq!(measure.char('B'), Some(623));
+        assert_eq!(measure.char('今'), None);
+        assert_eq!(measure.str("AB"), 540 + 623);
+
+        // Pairs are only kerned within a face
+        let width = |c| u32::from(measure.char(c).unwrap());
+        assert!(measure.kerning('T', 'o') < 0);
+        assert_eq!(
+            measure.str("To"),
+            (width('T') + width('o')).saturating_add_signed(measure.kerning('T', 'o').into())
+        );
+        assert_eq!(measure.kerning('A', 'T'), 0);
+        assert_eq!(measure.str("AT"), width('A') + width('T'));
+
+        // The glyphs are in the face that measured them
+        assert_eq!(measure.glyph('A'), demo_face.glyph_index('A'));
+        assert_eq!(measure.glyph('B'), font_face.glyph_index('B'));
+        assert_eq!(measure.glyph('今'), None);
+    }
+
+    #[test]
+    fn positions() {
+        let demo_data = crate::tests::read_demo_font();
+        let demo_face = Face::parse(&demo_data, 0).expect("TTF should be valid");
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+
+        let measure = FallbackMeasure::new(&[&demo_face, &font_face]);
+        let word_wrap = WhiteSpaceWordWrap::new(10000, &measure);
+
+        // Each position has the face to draw it with
+        let positions: Vec<CharPosition> = "AB今".wrap_with_position(&word_wrap).collect();
+        assert!(matches!(
+            positions[0],
+            CharPosition::Known(Position {
+                ch: 'A',
+                offset: 0,
+                width: 540,
+                face: Some(0),
+                ..
+            })
+        ));
+        assert!(matches!(
+            positions[1],
+            CharPosition::Known(Position {
+                ch: 'B',
+                offset: 540,
+                width: 623,
+                face: Some(1),
+                ..
+            })
+        ));
+        assert!(matches!(
+            positions[2],
+            CharPosition::Unknown(UnknownChar {
+                ch: '今',
+                index: 2,
+                char_index: 2,
+            })
+        ));
+    }
+}
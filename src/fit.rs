@@ -0,0 +1,203 @@
+use crate::{
+    LineBreak, LineMetrics, Measure, Scale, VerticalMetrics, WhiteSpaceWordWrap, WrapWithMetrics,
+};
+
+/// Sizes are searched in steps of 1/64th of a pixel.
+const STEPS_PER_PIXEL: f32 = 64.0;
+
+/// Finds the largest font size at which text, wrapped with `WhiteSpaceWordWrap`, fits in a box.
+///
+/// The text fits if its lines are no taller than the box, and no word had to be broken to fit the
+/// width of the box.
+#[derive(Debug)]
+pub struct FitToBox<'fnt> {
+    measure: &'fnt dyn Measure,
+    units_per_em: u16,
+    metrics: VerticalMetrics,
+    width: f32,
+    height: f32,
+    min_size: Option<f32>,
+    max_size: Option<f32>,
+}
+
+/// Text wrapped at the size that fits
+#[derive(Clone, PartialEq, Debug)]
+pub struct Fit<'txt> {
+    /// The scale of the font size that fits
+    pub scale: Scale,
+
+    /// The width of the box in font units, to wrap the text with
+    pub max_width: u32,
+
+    /// The wrapped lines
+    pub lines: Vec<&'txt str>,
+}
+
+impl<'fnt> FitToBox<'fnt> {
+    /// Creates a new `FitToBox` for a box of `width` × `height` pixels.
+    ///
+    /// Glyphs are measured with `measure`, from a font with `units_per_em`, and lines are spaced
+    /// using `metrics`.
+    pub fn new(
+        measure: &'fnt dyn Measure,
+        units_per_em: u16,
+        metrics: VerticalMetrics,
+        width: f32,
+        height: f32,
+    ) -> Self {
+        Self {
+            measure,
+            units_per_em,
+            metrics,
+            width,
+            height,
+            min_size: None,
+            max_size: None,
+        }
+    }
+
+    /// Sets the smallest font size, in pixels per em, 1 by default.
+    pub fn with_min_size(mut self, pixels_per_em: f32) -> Self {
+        self.min_size = Some(pixels_per_em);
+        self
+    }
+
+    /// Sets the largest font size, in pixels per em.
+    ///
+    /// By default, this is the size at which a single line fills the height of the box.
+    pub fn with_max_size(mut self, pixels_per_em: f32) -> Self {
+        self.max_size = Some(pixels_per_em);
+        self
+    }
+
+    /// Finds the largest font size at which `text` fits, or `None` if it does not fit at the
+    /// smallest size, or the smallest size is larger than the largest.
+    pub fn fit<'txt>(&self, text: &'txt str) -> Option<Fit<'txt>> {
+        let min_size = self.min_size.unwrap_or(1.0);
+        let max_size = self.max_size.unwrap_or_else(|| {
+            let line_height = self.metrics.line_height().max(1) as f32;
+            self.height * f32::from(self.units_per_em) / line_height
+        });
+
+        let mut low = (min_size * STEPS_PER_PIXEL).ceil() as u32;
+        let mut high = (max_size * STEPS_PER_PIXEL).floor() as u32;
+        if low > high {
+            return None;
+        }
+
+        let mut fit = self.fit_at(text, low)?;
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            match self.fit_at(text, middle) {
+                Some(middle_fit) => {
+                    low = middle;
+                    fit = middle_fit;
+                }
+                None => high = middle - 1,
+            }
+        }
+
+        Some(fit)
+    }
+
+    /// Wraps `text` at a size of `steps` / `STEPS_PER_PIXEL`, if it fits.
+    fn fit_at<'txt>(&self, text: &'txt str, steps: u32) -> Option<Fit<'txt>> {
+        let scale = Scale::new(self.units_per_em, steps as f32 / STEPS_PER_PIXEL);
+        let max_width = scale.to_units(self.width);
+
+        let word_wrap = WhiteSpaceWordWrap::new(max_width, self.measure);
+        let metrics: Vec<LineMetrics> = text.wrap_with_metrics(&word_wrap).collect();
+
+        let line_height = self.metrics.line_height() as f32 * scale.factor();
+        let height = metrics.len() as f32 * line_height;
+
+        let fits = height <= self.height
+            && metrics.iter().all(|line| {
+                line.width - line.trailing_white_space <= max_width
+                    && line.line_break != LineBreak::Forced
+            });
+
+        if fits {
+            Some(Fit {
+                scale,
+                max_width,
+                lines: metrics.into_iter().map(|line| &text[line.range]).collect(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::TTFParserMeasure;
+
+    use super::*;
+
+    #[test]
+    fn fits() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let metrics = VerticalMetrics::from_face(&font_face);
+
+        let poem = "Mary had a little lamb whose fleece was white as snow";
+        let fit_to_box = FitToBox::new(&measure, font_face.units_per_em(), metrics, 200.0, 100.0);
+        let fit = fit_to_box.fit(poem).unwrap();
+
+        // The lines fit in the box
+        let line_height = metrics.line_height() as f32 * fit.scale.factor();
+        assert!(fit.lines.len() as f32 * line_height <= 100.0);
+        assert!(fit
+            .lines
+            .iter()
+            .all(|line| fit.scale.to_pixels(measure.str(line) as i32) <= 200.0));
+
+        // A little larger does not
+        let larger = FitToBox::new(&measure, font_face.units_per_em(), metrics, 200.0, 100.0)
+            .with_min_size(fit.scale.to_pixels(2048) + 0.5);
+        assert!(larger.fit(poem).is_none());
+    }
+
+    #[test]
+    fn bounds() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let metrics = VerticalMetrics::from_face(&font_face);
+
+        // A single short word is limited by the max size
+        let fit_to_box = FitToBox::new(&measure, font_face.units_per_em(), metrics, 500.0, 500.0)
+            .with_max_size(20.0);
+        let fit = fit_to_box.fit("word").unwrap();
+        assert_eq!(fit.scale, Scale::new(2048, 20.0));
+        assert_eq!(fit.lines, vec!["word"]);
+
+        // Without a max size, one line fills the box
+        let fit_to_box = FitToBox::new(&measure, font_face.units_per_em(), metrics, 500.0, 20.0);
+        let fit = fit_to_box.fit("i").unwrap();
+        let line_height = metrics.line_height() as f32 * fit.scale.factor();
+        assert!((20.0 - line_height).abs() < 0.1);
+
+        // There is no size between a min size larger than the max size
+        let fit_to_box = FitToBox::new(&measure, font_face.units_per_em(), metrics, 500.0, 500.0)
+            .with_min_size(30.0)
+            .with_max_size(20.0);
+        assert!(fit_to_box.fit("word").is_none());
+    }
+
+    #[test]
+    fn does_not_fit() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let metrics = VerticalMetrics::from_face(&font_face);
+
+        let fit_to_box = FitToBox::new(&measure, font_face.units_per_em(), metrics, 10.0, 10.0)
+            .with_min_size(12.0);
+        assert!(fit_to_box.fit("too long to fit").is_none());
+    }
+}
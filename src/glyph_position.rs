@@ -0,0 +1,160 @@
+use std::{collections::VecDeque, fmt::Formatter, ops::Range};
+
+use ttf_parser::GlyphId;
+
+use crate::{
+    shaping::{Glyph, Shape},
+    token::{TokenKind, HYPHEN},
+};
+
+/// A shaped glyph's position in lines of text
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GlyphPosition {
+    /// The glyph in the font
+    pub glyph_id: GlyphId,
+
+    /// The bytes of the text that produced this glyph
+    ///
+    /// A ligature covers several chars, and several glyphs may share the same cluster.
+    pub cluster: Range<usize>,
+
+    /// The line that this glyph is on
+    pub line: u32,
+
+    /// The horizontal offset in the same units as the Font
+    pub offset: u32,
+
+    /// The advance width of the glyph
+    pub width: i32,
+
+    /// Horizontal offset from `offset` to draw the glyph at
+    pub x_offset: i32,
+
+    /// Vertical offset from the baseline to draw the glyph at
+    pub y_offset: i32,
+}
+
+pub trait GlyphPositions<T> {
+    fn glyph_positions<'a>(
+        self,
+        text: &'a str,
+        shape: &'a dyn Shape,
+    ) -> GlyphPositionIterator<'a, T>;
+}
+
+impl<T> GlyphPositions<T> for T
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn glyph_positions<'a>(
+        self,
+        text: &'a str,
+        shape: &'a dyn Shape,
+    ) -> GlyphPositionIterator<'a, T> {
+        GlyphPositionIterator {
+            glyphs: VecDeque::new(),
+            display_offset: 0,
+            line: 0,
+            shape,
+            text,
+            tokens: self,
+        }
+    }
+}
+
+/// Provides the positions of the glyphs of shaped tokens
+#[derive(Clone)]
+pub struct GlyphPositionIterator<'a, T> {
+    /// The glyphs of the current token, with their clusters in `text`
+    glyphs: VecDeque<GlyphPosition>,
+    display_offset: u32,
+    line: u32,
+    shape: &'a dyn Shape,
+    text: &'a str,
+    tokens: T,
+}
+
+impl<'a, T> std::fmt::Debug for GlyphPositionIterator<'a, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlyphPositionIterator").finish()
+    }
+}
+
+impl<'a, T> GlyphPositionIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    /// Shapes the text from `start` to `end` and queues up the glyphs.
+    fn shape(&mut self, start: usize, end: usize) {
+        let glyphs = self.shape.shape(&self.text[start..end]);
+
+        // A cluster ends where the next cluster, in logical order, starts
+        let mut clusters: Vec<usize> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+        clusters.sort_unstable();
+        clusters.dedup();
+
+        for glyph in glyphs {
+            let next = clusters
+                .iter()
+                .find(|&&cluster| cluster > glyph.cluster)
+                .map_or(end, |cluster| start + cluster);
+
+            self.push(glyph, start + glyph.cluster..next);
+        }
+    }
+
+    /// Shapes a hyphen for the soft hyphen from `start` to `end` and queues up the glyphs.
+    fn shape_hyphen(&mut self, start: usize, end: usize) {
+        let mut buffer = [0; 4];
+        for glyph in self.shape.shape(HYPHEN.encode_utf8(&mut buffer)) {
+            self.push(glyph, start..end);
+        }
+    }
+
+    /// Queues up `glyph` at the current offset.
+    fn push(&mut self, glyph: Glyph, cluster: Range<usize>) {
+        self.glyphs.push_back(GlyphPosition {
+            glyph_id: glyph.glyph_id,
+            cluster,
+            line: self.line,
+            offset: self.display_offset,
+            width: glyph.x_advance,
+            x_offset: glyph.x_offset,
+            y_offset: glyph.y_offset,
+        });
+
+        self.display_offset = self.display_offset.saturating_add_signed(glyph.x_advance);
+    }
+}
+
+impl<'a, T> Iterator for GlyphPositionIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    type Item = GlyphPosition;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(glyph) = self.glyphs.pop_front() {
+                return Some(glyph);
+            }
+
+            match self.tokens.next()? {
+                TokenKind::Newline(_) => {
+                    self.line += 1;
+                    self.display_offset = 0;
+                }
+                TokenKind::SoftHyphen(token) => {
+                    // The line breaks at the soft hyphen, draw a hyphen
+                    self.shape_hyphen(token.start, token.end);
+                }
+                TokenKind::Optional(token) | TokenKind::Required(token) => {
+                    self.shape(token.start, token.end);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::{collections::VecDeque, fmt::Formatter, ops::Range};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::CharPosition;
+
+/// A grapheme cluster's position in lines of text
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GraphemePosition<'a> {
+    /// The grapheme cluster, a hyphen has the soft hyphen it is drawn for
+    pub grapheme: &'a str,
+
+    /// The bytes of the grapheme in the text
+    pub range: Range<usize>,
+
+    /// The line that this grapheme is on
+    pub line: u32,
+
+    /// The horizontal offset in the same units as the Font
+    pub offset: u32,
+
+    /// The width of the chars of the grapheme
+    pub width: u32,
+}
+
+impl<'a> GraphemePosition<'a> {
+    /// Where the grapheme ends horizontally
+    pub fn end(&self) -> u32 {
+        self.offset + self.width
+    }
+}
+
+/// Provides a `GraphemePosition` for each grapheme of the `CharPosition`s of a text
+///
+/// Graphemes that the `Measure` does not know any of the chars of have a width of 0, at the end of
+/// the grapheme before them.
+#[derive(Clone)]
+pub struct GraphemePositionIterator<'a, T> {
+    text: &'a str,
+    /// Where each grapheme of `text` starts
+    starts: Vec<usize>,
+    positions: T,
+    /// The positions that were read ahead, starting with the first position of the next grapheme
+    pending: VecDeque<CharPosition>,
+    /// The line and offset that the previous grapheme ended at
+    cursor: Option<(u32, u32)>,
+}
+
+impl<'a, T> std::fmt::Debug for GraphemePositionIterator<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GraphemePositionIterator").finish()
+    }
+}
+
+impl<'a, T> GraphemePositionIterator<'a, T>
+where
+    T: Iterator<Item = CharPosition>,
+{
+    /// Groups the `positions` of `text` into graphemes.
+    pub(crate) fn new(text: &'a str, positions: T) -> Self {
+        let starts = text
+            .grapheme_indices(true)
+            .map(|(start, _)| start)
+            .collect();
+
+        Self {
+            text,
+            starts,
+            positions,
+            pending: VecDeque::new(),
+            cursor: None,
+        }
+    }
+
+    /// The positions the graphemes are made of.
+    pub(crate) fn positions(&self) -> &T {
+        &self.positions
+    }
+
+    /// The bytes of the grapheme that byte `index` is in.
+    fn grapheme(&self, index: usize) -> Range<usize> {
+        let after = self.starts.partition_point(|&start| start <= index);
+        let start = self.starts[after - 1];
+        let end = self.starts.get(after).copied().unwrap_or(self.text.len());
+        start..end
+    }
+
+    /// The next position, read ahead or not.
+    fn next_position(&mut self) -> Option<CharPosition> {
+        self.pending.pop_front().or_else(|| self.positions.next())
+    }
+
+    /// The line and offset of the next position of a char that the `Measure` knows.
+    fn next_known(&mut self) -> Option<(u32, u32)> {
+        loop {
+            let known = self.pending.iter().find_map(|position| match position {
+                CharPosition::Known(position) => Some((position.line, position.offset)),
+                CharPosition::Unknown(_) => None,
+            });
+            if known.is_some() {
+                return known;
+            }
+            self.pending.push_back(self.positions.next()?);
+        }
+    }
+}
+
+impl<'a, T> Iterator for GraphemePositionIterator<'a, T>
+where
+    T: Iterator<Item = CharPosition>,
+{
+    type Item = GraphemePosition<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.next_position()?;
+
+        let range = self.grapheme(first.index());
+        let mut known: Option<(u32, u32, u32)> = None;
+        let mut position = Some(first);
+
+        // The chars of a grapheme are next to each other, even when reordered right-to-left
+        while let Some(char_position) = position {
+            match char_position {
+                CharPosition::Known(char_position) => {
+                    let end = char_position.offset + u32::from(char_position.width);
+                    known = match known {
+                        None => Some((char_position.line, char_position.offset, end)),
+                        Some((line, start, grapheme_end)) if line == char_position.line => {
+                            Some((line, start.min(char_position.offset), grapheme_end.max(end)))
+                        }
+                        Some(_) => {
+                            self.pending.push_front(CharPosition::Known(char_position));
+                            break;
+                        }
+                    };
+                }
+                CharPosition::Unknown(_) => {}
+            }
+
+            position = self.next_position();
+            if let Some(next) = position.filter(|next| !range.contains(&next.index())) {
+                self.pending.push_front(next);
+                break;
+            }
+        }
+
+        let (line, offset, end) = match known {
+            Some(known) => known,
+            None => {
+                // Nothing of the grapheme is drawn, it is where the previous one ended, or where
+                // the first one that is drawn starts
+                let (line, offset) = match self.cursor {
+                    Some(cursor) => cursor,
+                    None => self.next_known().unwrap_or_default(),
+                };
+                (line, offset, offset)
+            }
+        };
+        self.cursor = Some((line, end));
+
+        Some(GraphemePosition {
+            grapheme: &self.text[range.clone()],
+            range,
+            line,
+            offset,
+            width: end - offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{Measure, TTFParserMeasure, WhiteSpaceWordWrap, WrapWithPosition};
+
+    use super::*;
+
+    #[test]
+    fn combining_marks() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &measure);
+
+        // The combining breve is part of the "y", not an unknown char of its own
+        let text = "y\u{306}es";
+        let graphemes: Vec<GraphemePosition<'_>> =
+            text.wrap_with_position(&word_wrap).graphemes().collect();
+
+        assert_eq!(
+            graphemes,
+            vec![
+                GraphemePosition {
+                    grapheme: "y\u{306}",
+                    range: 0..3,
+                    line: 0,
+                    offset: 0,
+                    width: measure.str("y"),
+                },
+                GraphemePosition {
+                    grapheme: "e",
+                    range: 3..4,
+                    line: 0,
+                    offset: measure.str("y"),
+                    width: measure.str("e"),
+                },
+                GraphemePosition {
+                    grapheme: "s",
+                    range: 4..5,
+                    line: 0,
+                    offset: measure.str("y") + measure.str("e"),
+                    width: measure.str("s"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &measure);
+
+        // A grapheme that is not drawn has no width, where the grapheme before it ends
+        let graphemes: Vec<(&str, u32, u32)> = "\u{4ECA}a\u{4ECA}"
+            .wrap_with_position(&word_wrap)
+            .graphemes()
+            .map(|grapheme| (grapheme.grapheme, grapheme.offset, grapheme.width))
+            .collect();
+        let a = measure.str("a");
+        assert_eq!(
+            graphemes,
+            vec![("\u{4ECA}", 0, 0), ("a", 0, a), ("\u{4ECA}", a, 0)]
+        );
+
+        // Or where the first grapheme that is drawn starts
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &measure).with_first_line_indent(500);
+        let grapheme = "\u{4ECA}a"
+            .wrap_with_position(&word_wrap)
+            .graphemes()
+            .next()
+            .unwrap();
+        assert_eq!((grapheme.offset, grapheme.width), (500, 0));
+    }
+
+    #[test]
+    fn lines() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(0, &measure);
+
+        // Each grapheme is on a line of its own, with its combining mark
+        let graphemes: Vec<(&str, u32)> = "aa\u{301}"
+            .wrap_with_position(&word_wrap)
+            .graphemes()
+            .map(|grapheme| (grapheme.grapheme, grapheme.line))
+            .collect();
+        assert_eq!(graphemes, vec![("a", 0), ("a\u{301}", 1)]);
+    }
+}
@@ -1,14 +1,20 @@
 //! Useful when creating new wrapping iterators.
 
-use crate::measure::Measure;
+use crate::{
+    measure::{is_kerned, Measure},
+    token::{HYPHEN, SOFT_HYPHEN},
+};
 
-use unicode_segmentation::{Graphemes, UnicodeSegmentation};
+use unicode_segmentation::{GraphemeIndices, UnicodeSegmentation};
 
 /// A char and it's display width, along wtith the `font_face` and `&str` it came from.
 #[derive(Clone, Debug)]
 pub struct GraphemeWidth<'a> {
     pub grapheme: &'a str,
     pub display_width: u32,
+
+    /// The kerning between the previous grapheme and this one.
+    pub kerning: i16,
 }
 
 pub trait WithGraphemeWidth {
@@ -17,15 +23,22 @@ pub trait WithGraphemeWidth {
 
 impl WithGraphemeWidth for str {
     fn with_grapheme_width<'a>(&'a self, measure: &'a dyn Measure) -> GraphemeWidthIterator<'a> {
-        let graphemes = self.graphemes(true);
-        GraphemeWidthIterator { measure, graphemes }
+        let graphemes = self.grapheme_indices(true);
+        GraphemeWidthIterator {
+            measure,
+            graphemes,
+            previous: None,
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct GraphemeWidthIterator<'a> {
     measure: &'a dyn Measure,
-    graphemes: Graphemes<'a>,
+    graphemes: GraphemeIndices<'a>,
+
+    /// The last char of the previous grapheme
+    previous: Option<char>,
 }
 
 impl<'a> std::fmt::Debug for GraphemeWidthIterator<'a> {
@@ -38,13 +51,27 @@ impl<'a> Iterator for GraphemeWidthIterator<'a> {
     type Item = GraphemeWidth<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let grapheme = self.graphemes.next()?;
+        let (index, grapheme) = self.graphemes.next()?;
+
+        // A soft hyphen is as wide as the hyphen that is drawn when a line breaks at it
+        let display_width = if grapheme == SOFT_HYPHEN {
+            self.measure.char_at(index, HYPHEN).map_or(0, u32::from)
+        } else {
+            self.measure.str_at(index, grapheme)
+        };
 
-        let display_width = self.measure.str(grapheme);
+        let kerning = match (self.previous, grapheme.chars().next()) {
+            (Some(left), Some(right)) if is_kerned(left, right) => {
+                self.measure.kerning_at(index, left, right)
+            }
+            _ => 0,
+        };
+        self.previous = grapheme.chars().next_back();
 
         Some(GraphemeWidth {
             grapheme,
             display_width,
+            kerning,
         })
     }
 }
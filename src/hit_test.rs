@@ -0,0 +1,506 @@
+use std::{fmt::Formatter, ops::Range, rc::Rc};
+
+use unicode_bidi::BidiInfo;
+
+use crate::{
+    line_widths::LineWidths, metrics::LineBreaks, position::PositionIterator, token::TokenKind,
+    Alignment, GraphemePosition, GraphemePositionIterator, LineBreak, LineMetrics,
+};
+
+/// The half of a grapheme that a point is on, in the direction of the text.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Side {
+    /// The half the grapheme starts on, the left half of left-to-right text.
+    Leading,
+
+    /// The half the grapheme ends on, the right half of left-to-right text.
+    Trailing,
+}
+
+/// The grapheme boundary nearest to a point on a line.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Hit {
+    /// The byte index of the boundary, the start of `grapheme` if `side` is `Leading` and the end
+    /// of it if `side` is `Trailing`
+    pub index: usize,
+
+    /// The bytes of the grapheme nearest to the point, empty on a line without glyphs
+    pub grapheme: Range<usize>,
+
+    /// The half of the grapheme the point is on
+    pub side: Side,
+}
+
+/// How far `offset` is outside of `grapheme`.
+fn distance(grapheme: &GraphemePosition<'_>, offset: u32) -> u32 {
+    if offset < grapheme.offset {
+        grapheme.offset - offset
+    } else {
+        // The grapheme ends before its `end`
+        (offset + 1).saturating_sub(grapheme.end())
+    }
+}
+
+/// The position of a caret between graphemes.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Caret {
+    /// The line that the caret is on
+    pub line: u32,
+
+    /// The horizontal offset of the caret in the same units as the Font
+    pub offset: u32,
+}
+
+/// A part of a line that is selected.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SelectionRect {
+    /// The line that the rect is on
+    pub line: u32,
+
+    /// The horizontal offset of the rect in the same units as the Font
+    pub offset: u32,
+
+    /// The width of the rect
+    pub width: u32,
+}
+
+/// Text laid out by a wrapper, to hit test points, place carets and select ranges in.
+///
+/// Created once with `WhiteSpaceWordWrap::layout()`, it can be queried as often as needed without
+/// wrapping the text again.
+#[derive(Clone)]
+pub struct Layout<'a> {
+    text: &'a str,
+
+    /// The graphemes of the text, in visual order
+    boxes: Vec<GraphemePosition<'a>>,
+    bidi: Option<Rc<BidiInfo<'a>>>,
+    lines: Vec<LineMetrics>,
+
+    /// The offset each line starts at, where the caret goes on a line without glyphs
+    line_starts: Vec<u32>,
+}
+
+impl<'a> std::fmt::Debug for Layout<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Layout")
+            .field("graphemes", &self.boxes)
+            .field("lines", &self.lines)
+            .finish()
+    }
+}
+
+impl<'a> Layout<'a> {
+    /// Collects the `graphemes` and `lines` of the same layout of `text`, which placed its lines
+    /// within `line_widths` using `alignment`.
+    pub(crate) fn new<T, M>(
+        text: &'a str,
+        graphemes: GraphemePositionIterator<'a, PositionIterator<'a, T>>,
+        lines: M,
+        line_widths: &LineWidths,
+        alignment: Alignment,
+    ) -> Self
+    where
+        T: Iterator<Item = TokenKind> + LineBreaks,
+        M: Iterator<Item = LineMetrics>,
+    {
+        // Only text with right-to-left chars has graphemes that start on the right
+        let bidi = graphemes.positions().bidi();
+        let boxes = graphemes.collect();
+        let lines: Vec<LineMetrics> = lines.collect();
+
+        // An empty line is aligned as the positions of a line would be
+        let mut is_first_line = true;
+        let line_starts = (0..)
+            .zip(&lines)
+            .map(|(line, line_metrics)| {
+                let max_width = line_widths.width(line, is_first_line);
+                let (offset, _) = alignment.align(std::iter::empty(), max_width, false);
+                let start = line_widths.indent(is_first_line) + offset;
+                is_first_line = line_metrics.line_break == LineBreak::Newline;
+                start
+            })
+            .collect();
+
+        Self {
+            text,
+            boxes,
+            bidi,
+            lines,
+            line_starts,
+        }
+    }
+
+    /// The graphemes of the text in visual order, line by line.
+    pub fn graphemes(&self) -> &[GraphemePosition<'a>] {
+        &self.boxes
+    }
+
+    /// The metrics of each line.
+    pub fn lines(&self) -> &[LineMetrics] {
+        &self.lines
+    }
+
+    /// The graphemes on `line`, from left to right.
+    fn line(&self, line: u32) -> &[GraphemePosition<'a>] {
+        let start = self.boxes.partition_point(|grapheme| grapheme.line < line);
+        let end = self.boxes.partition_point(|grapheme| grapheme.line <= line);
+        &self.boxes[start..end]
+    }
+
+    fn is_rtl(&self, grapheme: &GraphemePosition<'_>) -> bool {
+        self.bidi
+            .as_ref()
+            .is_some_and(|bidi| bidi.levels[grapheme.range.start].is_rtl())
+    }
+
+    /// The offset of the edge of `grapheme` that it starts at.
+    fn leading_edge(&self, grapheme: &GraphemePosition<'_>) -> u32 {
+        if self.is_rtl(grapheme) {
+            grapheme.end()
+        } else {
+            grapheme.offset
+        }
+    }
+
+    /// The offset of the edge of `grapheme` that it ends at.
+    fn trailing_edge(&self, grapheme: &GraphemePosition<'_>) -> u32 {
+        if self.is_rtl(grapheme) {
+            grapheme.offset
+        } else {
+            grapheme.end()
+        }
+    }
+
+    /// The grapheme boundary nearest to `offset` on `line`.
+    ///
+    /// Returns `None` if there is no `line`.
+    pub fn hit_test(&self, line: u32, offset: u32) -> Option<Hit> {
+        let line_metrics = self.lines.get(line as usize)?;
+
+        let nearest = match self
+            .line(line)
+            .iter()
+            .min_by_key(|grapheme| distance(grapheme, offset))
+        {
+            Some(nearest) => nearest,
+            None => {
+                // There is nothing to hit, the caret goes at the start of the line
+                let start = line_metrics.range.start;
+                return Some(Hit {
+                    index: start,
+                    grapheme: start..start,
+                    side: Side::Leading,
+                });
+            }
+        };
+
+        let offset = offset.clamp(nearest.offset, nearest.end());
+        let is_left_half = offset < nearest.offset + nearest.width / 2;
+
+        let (index, side) = if is_left_half != self.is_rtl(nearest) {
+            (nearest.range.start, Side::Leading)
+        } else {
+            (nearest.range.end, Side::Trailing)
+        };
+
+        Some(Hit {
+            index,
+            grapheme: nearest.range.clone(),
+            side,
+        })
+    }
+
+    /// The caret before the grapheme at byte `index`, or after the grapheme that ends at it.
+    ///
+    /// An `index` within a grapheme is moved to the start of it. Returns `None` if `index` is past
+    /// the end of the text.
+    pub fn caret(&self, index: usize) -> Option<Caret> {
+        if index > self.text.len() {
+            return None;
+        }
+
+        let before = self
+            .boxes
+            .iter()
+            .find(|grapheme| grapheme.range.start <= index && index < grapheme.range.end);
+        if let Some(grapheme) = before {
+            return Some(Caret {
+                line: grapheme.line,
+                offset: self.leading_edge(grapheme),
+            });
+        }
+
+        let after = self
+            .boxes
+            .iter()
+            .find(|grapheme| grapheme.range.end == index);
+        if let Some(grapheme) = after {
+            return Some(Caret {
+                line: grapheme.line,
+                offset: self.trailing_edge(grapheme),
+            });
+        }
+
+        // A line without glyphs, the caret goes at the start of it
+        self.lines
+            .iter()
+            .zip(0..)
+            .find(|(line_metrics, _)| {
+                line_metrics.range.start <= index && index <= line_metrics.range.end
+            })
+            .map(|(_, line)| Caret {
+                line,
+                offset: self.line_starts[line as usize],
+            })
+    }
+
+    /// The rects that cover the graphemes in `range` of the text, from left to right on each line,
+    /// to highlight a selection.
+    ///
+    /// Graphemes that are partly in `range` are covered. Right-to-left text can have several rects
+    /// on a line.
+    pub fn selection(&self, range: Range<usize>) -> Vec<SelectionRect> {
+        let mut rects: Vec<SelectionRect> = Vec::new();
+        let mut is_selecting = false;
+
+        for grapheme in &self.boxes {
+            let is_selected = grapheme.range.start < range.end && range.start < grapheme.range.end;
+            if !is_selected {
+                is_selecting = false;
+                continue;
+            }
+
+            match rects.last_mut() {
+                Some(rect) if is_selecting && rect.line == grapheme.line => {
+                    rect.width = grapheme.end() - rect.offset;
+                }
+                _ => rects.push(SelectionRect {
+                    line: grapheme.line,
+                    offset: grapheme.offset,
+                    width: grapheme.width,
+                }),
+            }
+            is_selecting = true;
+        }
+
+        rects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{Measure, TTFParserMeasure, WhiteSpaceWordWrap, Wrap};
+
+    use super::*;
+
+    #[test]
+    fn hit_test() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "hello world\n\nend";
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("hello w"), &measure);
+        let lines: Vec<&str> = text.wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["hello", "world", "", "end"]);
+
+        let h = measure.str("h");
+        let layout = word_wrap.layout(text);
+        let hit = |line, offset| {
+            layout
+                .hit_test(line, offset)
+                .map(|hit| (hit.index, hit.side))
+        };
+
+        // Each half of a glyph is nearest to one of its boundaries
+        assert_eq!(hit(0, 0), Some((0, Side::Leading)));
+        assert_eq!(hit(0, h / 2 - 1), Some((0, Side::Leading)));
+        assert_eq!(hit(0, h / 2 + 1), Some((1, Side::Trailing)));
+        assert_eq!(hit(0, h + 1), Some((1, Side::Leading)));
+
+        // Points past the end of a line are nearest to its last glyph
+        assert_eq!(hit(1, 100_000), Some((11, Side::Trailing)));
+
+        // An empty line only has its start
+        assert_eq!(hit(2, 1000), Some((12, Side::Leading)));
+
+        // There are only four lines
+        assert_eq!(hit(3, 0), Some((13, Side::Leading)));
+        assert_eq!(hit(4, 0), None);
+    }
+
+    #[test]
+    fn graphemes() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &measure);
+
+        // The combining breve is part of the "y", there is no boundary before it
+        let text = "y\u{306}es";
+        let y = measure.str("y");
+        let hit = word_wrap.layout(text).hit_test(0, y - 1).unwrap();
+        assert_eq!(
+            hit,
+            Hit {
+                index: 3,
+                grapheme: 0..3,
+                side: Side::Trailing,
+            }
+        );
+    }
+
+    #[test]
+    fn right_to_left() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &measure);
+
+        // "c" is drawn first, its left half is the half it ends on
+        let text = "\u{202E}abc\u{202C}";
+        let hit = word_wrap.layout(text).hit_test(0, 1).unwrap();
+        assert_eq!(
+            hit,
+            Hit {
+                index: 6,
+                grapheme: 5..6,
+                side: Side::Trailing,
+            }
+        );
+    }
+
+    #[test]
+    fn caret() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "hello world\n\nend";
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("hello w"), &measure);
+        let layout = word_wrap.layout(text);
+        let caret = |index| layout.caret(index).map(|caret| (caret.line, caret.offset));
+
+        // A caret is before the grapheme that starts at the index
+        assert_eq!(caret(0), Some((0, 0)));
+        assert_eq!(caret(2), Some((0, measure.str("he"))));
+        assert_eq!(caret(6), Some((1, 0)));
+
+        // Or after the grapheme that ends at it
+        assert_eq!(caret(11), Some((1, measure.str("world"))));
+        assert_eq!(caret(16), Some((3, measure.str("end"))));
+
+        // An empty line only has its start
+        assert_eq!(caret(12), Some((2, 0)));
+        assert_eq!(caret(17), None);
+
+        // Indices within a grapheme are at the start of it
+        let layout = word_wrap.layout("y\u{306}es");
+        assert_eq!(layout.caret(1), Some(Caret { line: 0, offset: 0 }));
+        assert_eq!(
+            layout.caret(3),
+            Some(Caret {
+                line: 0,
+                offset: measure.str("y")
+            })
+        );
+    }
+
+    #[test]
+    fn caret_on_empty_lines() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "hello world\n\nend";
+        let max_width = measure.str("hello w");
+        let caret = |word_wrap: WhiteSpaceWordWrap<'_>| word_wrap.layout(text).caret(12);
+
+        // The empty line starts a paragraph, it has the first line indent
+        let word_wrap = WhiteSpaceWordWrap::new(max_width, &measure)
+            .with_first_line_indent(500)
+            .with_hanging_indent(300);
+        assert_eq!(
+            caret(word_wrap),
+            Some(Caret {
+                line: 2,
+                offset: 500
+            })
+        );
+
+        // It is aligned as if it had no width
+        let word_wrap =
+            WhiteSpaceWordWrap::new(max_width, &measure).with_alignment(Alignment::Center);
+        assert_eq!(
+            caret(word_wrap),
+            Some(Caret {
+                line: 2,
+                offset: max_width / 2
+            })
+        );
+
+        let word_wrap = WhiteSpaceWordWrap::new(max_width, &measure)
+            .with_hanging_indent(300)
+            .with_alignment(Alignment::Right);
+        assert_eq!(
+            caret(word_wrap),
+            Some(Caret {
+                line: 2,
+                offset: max_width
+            })
+        );
+    }
+
+    #[test]
+    fn selection() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "hello world\n\nend";
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("hello w"), &measure);
+
+        // The selection has a rect on each line with glyphs, the whitespace a line wraps at is
+        // not drawn
+        assert_eq!(
+            word_wrap.layout(text).selection(3..15),
+            vec![
+                SelectionRect {
+                    line: 0,
+                    offset: measure.str("hel"),
+                    width: measure.str("lo"),
+                },
+                SelectionRect {
+                    line: 1,
+                    offset: 0,
+                    width: measure.str("world"),
+                },
+                SelectionRect {
+                    line: 3,
+                    offset: 0,
+                    width: measure.str("en"),
+                },
+            ]
+        );
+
+        // A grapheme is selected as a whole
+        let layout = word_wrap.layout("y\u{306}es");
+        assert_eq!(
+            layout.selection(2..3),
+            vec![SelectionRect {
+                line: 0,
+                offset: 0,
+                width: measure.str("y"),
+            }]
+        );
+
+        // In a right-to-left paragraph "ab" is drawn to the right of "cd", away from the "c"
+        let text = "\u{5E9}ab \u{5DC}\u{5DD} cd";
+        let rects = word_wrap.layout(text).selection(2..text.find('d').unwrap());
+        assert_eq!(rects.len(), 2);
+        assert_eq!((rects[0].offset, rects[0].width), (0, measure.str("c")));
+    }
+}
@@ -0,0 +1,453 @@
+use std::{collections::HashMap, collections::VecDeque, fmt::Formatter, path::Path};
+
+use crate::{
+    token::{Token, TokenKind, HYPHEN},
+    Measure,
+};
+
+/// Finds hyphenation points in words with Frank Liang's algorithm, as used by TeX.
+///
+/// Patterns are read from TeX hyphenation files, such as `hyph-en-us.tex`, that contain a
+/// `\patterns{...}` group and optionally a `\hyphenation{...}` group of exceptions. Files without
+/// any groups, such as `hyph-en-us.pat.txt`, are read as a list of patterns.
+#[derive(Clone, Debug, Default)]
+pub struct Hyphenator {
+    /// The letters of a pattern and the values between them
+    patterns: HashMap<String, Vec<u8>>,
+
+    /// Words and the char indices they are hyphenated at
+    exceptions: HashMap<String, Vec<usize>>,
+
+    /// The length of the longest pattern, in chars
+    max_pattern: usize,
+
+    /// The minimum number of chars before a hyphen
+    left_min: usize,
+
+    /// The minimum number of chars after a hyphen
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Creates a new `Hyphenator` from the contents of a TeX hyphenation file.
+    pub fn new(patterns: &str) -> Self {
+        let mut hyphenator = Self {
+            left_min: 2,
+            right_min: 3,
+            ..Self::default()
+        };
+
+        // Remove the comments
+        let patterns: String = patterns
+            .lines()
+            .map(|line| line.split('%').next().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let groups = [("\\patterns", false), ("\\hyphenation", true)];
+        if groups.iter().any(|(name, _)| patterns.contains(name)) {
+            for (name, is_exception) in groups.iter() {
+                let mut rest = patterns.as_str();
+                while let Some(start) = rest.find(name) {
+                    let group = &rest[start + name.len()..];
+                    let group = group.trim_start().strip_prefix('{').unwrap_or(group);
+                    let end = group.find('}').unwrap_or(group.len());
+
+                    for word in group[..end].split_whitespace() {
+                        if *is_exception {
+                            hyphenator.add_exception(word);
+                        } else {
+                            hyphenator.add_pattern(word);
+                        }
+                    }
+
+                    rest = &group[end..];
+                }
+            }
+        } else {
+            patterns
+                .split_whitespace()
+                .for_each(|pattern| hyphenator.add_pattern(pattern));
+        }
+
+        hyphenator
+    }
+
+    /// Creates a new `Hyphenator` from a TeX hyphenation file on disk.
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        std::fs::read_to_string(path).map(|patterns| Self::new(&patterns))
+    }
+
+    /// Sets the minimum number of chars before a hyphen, 2 by default.
+    pub fn with_left_min(mut self, left_min: usize) -> Self {
+        self.left_min = left_min;
+        self
+    }
+
+    /// Sets the minimum number of chars after a hyphen, 3 by default.
+    pub fn with_right_min(mut self, right_min: usize) -> Self {
+        self.right_min = right_min;
+        self
+    }
+
+    /// Adds a pattern such as `hy3ph`.
+    fn add_pattern(&mut self, pattern: &str) {
+        let mut letters = String::new();
+        let mut values = vec![0];
+
+        for ch in pattern.chars() {
+            match ch.to_digit(10) {
+                Some(value) => *values.last_mut().unwrap() = value as u8,
+                None => {
+                    letters.push(ch);
+                    values.push(0);
+                }
+            }
+        }
+
+        self.max_pattern = self.max_pattern.max(values.len() - 1);
+        self.patterns.insert(letters, values);
+    }
+
+    /// Adds an exception such as `ta-ble`.
+    fn add_exception(&mut self, exception: &str) {
+        let mut word = String::new();
+        let mut points = Vec::new();
+
+        for ch in exception.chars() {
+            if ch == '-' {
+                points.push(word.chars().count());
+            } else {
+                word.push(ch);
+            }
+        }
+
+        self.exceptions.insert(word, points);
+    }
+
+    /// The byte indices in `word` where it may be hyphenated.
+    ///
+    /// Punctuation before and after the letters of the word, such as in `"(word),"`, is not
+    /// hyphenated.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let letters = word.trim_start_matches(|c: char| !c.is_alphabetic());
+        let start = word.len() - letters.len();
+        let letters = letters.trim_end_matches(|c: char| !c.is_alphabetic());
+
+        self.hyphenate_letters(letters)
+            .into_iter()
+            .map(|point| start + point)
+            .collect()
+    }
+
+    /// The byte indices in `word`, which starts and ends with a letter, where it may be hyphenated.
+    fn hyphenate_letters(&self, word: &str) -> Vec<usize> {
+        let lowercase: String = word.chars().flat_map(char::to_lowercase).collect();
+
+        // Words that lowercase to a different number of chars are not hyphenated
+        let char_count = word.chars().count();
+        if lowercase.chars().count() != char_count || char_count < self.left_min + self.right_min {
+            return Vec::new();
+        }
+
+        let points: Vec<usize> = match self.exceptions.get(&lowercase) {
+            Some(points) => points.clone(),
+            None => {
+                // The word with its boundaries marked, and where each of its chars starts
+                let dotted = format!(".{}.", lowercase);
+                let bounds: Vec<usize> = dotted
+                    .char_indices()
+                    .map(|(index, _)| index)
+                    .chain(std::iter::once(dotted.len()))
+                    .collect();
+                let dotted_len = bounds.len() - 1;
+
+                // values[i] is the value before char i of dotted
+                let mut values = vec![0u8; dotted_len + 1];
+                for start in 0..dotted_len {
+                    let end = dotted_len.min(start + self.max_pattern);
+                    for stop in start + 1..=end {
+                        let letters = &dotted[bounds[start]..bounds[stop]];
+                        if let Some(pattern) = self.patterns.get(letters) {
+                            for (offset, &value) in pattern.iter().enumerate() {
+                                let value_index = start + offset;
+                                values[value_index] = values[value_index].max(value);
+                            }
+                        }
+                    }
+                }
+
+                // Odd values allow a hyphen, before char `i` of the word is before dotted[i + 1]
+                (1..char_count)
+                    .filter(|&i| values[i + 1] % 2 == 1)
+                    .collect()
+            }
+        };
+
+        let byte_indices: Vec<usize> = word.char_indices().map(|(index, _)| index).collect();
+        points
+            .into_iter()
+            .filter(|&i| i >= self.left_min && i + self.right_min <= char_count)
+            .filter_map(|i| byte_indices.get(i).copied())
+            .collect()
+    }
+}
+
+pub trait WithHyphenation<T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn with_hyphenation<'a>(
+        self,
+        max_width: u32,
+        text: &'a str,
+        measure: &'a dyn Measure,
+        hyphenator: Option<&'a Hyphenator>,
+    ) -> HyphenationIterator<'a, T>;
+}
+
+impl<T> WithHyphenation<T> for T
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn with_hyphenation<'a>(
+        self,
+        max_width: u32,
+        text: &'a str,
+        measure: &'a dyn Measure,
+        hyphenator: Option<&'a Hyphenator>,
+    ) -> HyphenationIterator<'a, T> {
+        HyphenationIterator {
+            max_width,
+            text,
+            measure,
+            hyphenator,
+            tokens: self,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+/// Splits words that are wider than the max_width at their hyphenation points.
+///
+/// The parts of the word are separated by `SoftHyphen` tokens that do not cover any text, so a
+/// hyphen is drawn where a line breaks within the word. Parts that are still too wide are broken
+/// anywhere by the `PartialTokensIterator`.
+#[derive(Clone)]
+pub struct HyphenationIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    max_width: u32,
+    text: &'a str,
+    measure: &'a dyn Measure,
+    hyphenator: Option<&'a Hyphenator>,
+    tokens: T,
+
+    /// The parts of the hyphenated word
+    pending: VecDeque<TokenKind>,
+}
+
+impl<'a, T> std::fmt::Debug for HyphenationIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HyphenationIterator")
+            .field("max_width", &self.max_width)
+            .finish()
+    }
+}
+
+impl<'a, T> HyphenationIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    /// Splits `token` at the `hyphenator`s points into `pending`.
+    fn hyphenate(&mut self, token: Token, hyphenator: &Hyphenator) {
+        let mut start = token.start;
+        for point in hyphenator.hyphenate(token.as_str(self.text)) {
+            let end = token.start + point;
+            let display_width = self.measure.str_at(start, &self.text[start..end]);
+            let hyphen_width = self.measure.char_at(end, HYPHEN).map_or(0, u32::from);
+
+            self.pending
+                .push_back(TokenKind::Required(Token::new(start, end, display_width)));
+            self.pending
+                .push_back(TokenKind::SoftHyphen(Token::new(end, end, hyphen_width)));
+            start = end;
+        }
+
+        let display_width = if start == token.start {
+            token.display_width
+        } else {
+            self.measure.str_at(start, &self.text[start..token.end])
+        };
+        self.pending.push_back(TokenKind::Required(Token::new(
+            start,
+            token.end,
+            display_width,
+        )));
+    }
+}
+
+impl<'a, T> Iterator for HyphenationIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    type Item = TokenKind;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token_kind) = self.pending.pop_front() {
+            return Some(token_kind);
+        }
+
+        match (self.tokens.next()?, self.hyphenator) {
+            (TokenKind::Required(token), Some(hyphenator))
+                if token.display_width > self.max_width =>
+            {
+                self.hyphenate(token, hyphenator);
+                self.pending.pop_front()
+            }
+            (token_kind, _) => Some(token_kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use ttf_parser::Face;
+
+    use crate::{
+        grapheme_width::WithGraphemeWidth, line::Lines, line_break::AddNewlines,
+        partial_tokens::WithPartialTokens, whitespace::TokenizeWhiteSpace, CharPosition, Position,
+        TTFParserMeasure, WhiteSpaceWordWrap, WrapWithPosition,
+    };
+
+    use super::*;
+
+    fn read_patterns() -> Hyphenator {
+        Hyphenator::load("./test_patterns/hyph-test.tex").expect("Patterns should exist")
+    }
+
+    #[test]
+    fn patterns() {
+        let hyphenator = read_patterns();
+
+        assert_eq!(hyphenator.hyphenate("hyphenation"), vec![2, 6]);
+        assert_eq!(hyphenator.hyphenate("Hyphenation"), vec![2, 6]);
+        assert!(hyphenator.hyphenate("word").is_empty());
+    }
+
+    #[test]
+    fn exceptions() {
+        let hyphenator = read_patterns().with_right_min(2);
+
+        assert_eq!(hyphenator.hyphenate("table"), vec![2]);
+    }
+
+    #[test]
+    fn fragment_lengths() {
+        let hyphenator = read_patterns().with_left_min(3);
+        assert_eq!(hyphenator.hyphenate("hyphenation"), vec![6]);
+
+        let hyphenator = read_patterns().with_right_min(6);
+        assert_eq!(hyphenator.hyphenate("hyphenation"), vec![2]);
+    }
+
+    #[test]
+    fn punctuation() {
+        // The word boundaries are at the letters
+        let hyphenator = Hyphenator::new(".ex1am");
+        assert_eq!(hyphenator.hyphenate("example"), vec![2]);
+        assert_eq!(hyphenator.hyphenate("(example)."), vec![3]);
+
+        // Punctuation does not count towards the fragment lengths
+        let hyphenator = read_patterns().with_right_min(6);
+        assert_eq!(hyphenator.hyphenate("hyphenation,"), vec![2]);
+        assert!(hyphenator.hyphenate("...").is_empty());
+    }
+
+    #[test]
+    fn plain_patterns() {
+        let hyphenator = Hyphenator::new("hy3ph he2n hena4 hen5at 1na n2at 1tio 2io");
+
+        assert_eq!(hyphenator.hyphenate("hyphenation"), vec![2, 6]);
+    }
+
+    #[test]
+    fn long_words() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let hyphenator = read_patterns();
+
+        // "hyphenation" is wider than the lines, "hyphen-" is not
+        let text = "hyphenation";
+        let lines = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_hyphenation(8000, text, &measure, Some(&hyphenator))
+            .with_partial_tokens(8000, text, &measure)
+            .add_newlines_at(8000)
+            .lines(text);
+
+        // The hyphenation point has no soft hyphen in the text, the line breaks at it anyway
+        let unhyphenated: Vec<&str> = lines.clone().collect();
+        assert_eq!(unhyphenated, vec!["hyphen", "ation"]);
+
+        let hyphenated: Vec<Cow<'_, str>> = lines.with_hyphens().collect();
+        assert_eq!(hyphenated, vec!["hyphen-", "ation"]);
+    }
+
+    #[test]
+    fn short_words() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let hyphenator = read_patterns();
+
+        // Words that fit on a line are not hyphenated
+        let text = "hyphenation";
+        let tokens: Vec<TokenKind> = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_hyphenation(20000, text, &measure, Some(&hyphenator))
+            .collect();
+
+        assert_eq!(tokens.len(), 1);
+    }
+
+    #[test]
+    fn positions() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let hyphenator = read_patterns();
+
+        let word_wrap = WhiteSpaceWordWrap::new(8000, &measure).with_hyphenator(&hyphenator);
+        let positions: Vec<CharPosition> = "hyphenation".wrap_with_position(&word_wrap).collect();
+
+        // A hyphen is drawn at the end of the first line
+        assert_eq!(positions.len(), 12);
+        assert!(matches!(
+            positions[6],
+            CharPosition::Known(Position {
+                ch: '-',
+                line: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            positions[7],
+            CharPosition::Known(Position {
+                ch: 'a',
+                line: 1,
+                offset: 0,
+                ..
+            })
+        ));
+    }
+}
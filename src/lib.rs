@@ -41,28 +41,72 @@
 //! let positions: Vec<CharPosition> = poem.wrap_with_position(&word_wrap).collect();
 //!
 //! // offset is in the unit (em) of the TTFParserMeasure.
-//! // If the font does not have the given char, `CharPosition::Unknown('M')` is returned.
-//! assert!(matches!(positions[0], CharPosition::Known(Position { ch: 'M', line: 0, offset: 0, width: 1788 })));
+//! // If the font does not have the given char, `CharPosition::Unknown(_)` is returned.
+//! assert!(matches!(positions[0], CharPosition::Known(Position { ch: 'M', line: 0, offset: 0, width: 1788, .. })));
 //!```
 #![warn(missing_docs, missing_debug_implementations, rust_2018_idioms)]
 #![doc(test(attr(deny(rust_2018_idioms, warnings))))]
 #![doc(test(attr(allow(unused_extern_crates, unused_variables))))]
 
+mod alignment;
+mod fallback_measure;
+mod fit;
+mod glyph_position;
+mod grapheme_position;
 mod grapheme_width;
+mod hit_test;
+mod hyphenation;
 mod line;
 mod line_break;
+mod line_widths;
 mod measure;
+mod metrics;
+mod optimal_line_break;
+mod optimal_wordwrap;
 mod partial_tokens;
 mod position;
+#[cfg(feature = "shaping")]
+mod rustybuzz_measure;
+mod scale;
+mod shaping;
+mod shaping_wordwrap;
+mod span;
 mod token;
+mod truncate;
+mod truncated_wordwrap;
+mod unicode_line_break;
+mod unicode_wordwrap;
+mod vertical_metrics;
+mod white_space_mode;
 mod whitespace;
 mod whitespace_wordwrap;
 mod wordwrap;
 
+pub use alignment::Alignment;
+pub use fallback_measure::FallbackMeasure;
+pub use fit::{Fit, FitToBox};
+pub use glyph_position::GlyphPosition;
+pub use grapheme_position::{GraphemePosition, GraphemePositionIterator};
+pub use hit_test::{Caret, Hit, Layout, SelectionRect, Side};
+pub use hyphenation::Hyphenator;
 pub use measure::{Measure, TTFParserMeasure};
-pub use position::{CharPosition, Position};
+pub use metrics::{LineBreak, LineMetrics};
+pub use optimal_wordwrap::OptimalWordWrap;
+pub use position::{CharPosition, Position, UnknownChar};
+#[cfg(feature = "shaping")]
+pub use rustybuzz_measure::RustybuzzMeasure;
+pub use scale::{
+    Rounding, Scale, ScalePositions, ScaledCharPosition, ScaledPosition, ScaledPositionIterator,
+};
+pub use shaping::{Glyph, Shape};
+pub use shaping_wordwrap::ShapingWordWrap;
+pub use span::{Span, SpanMeasure};
+pub use truncated_wordwrap::TruncatedWordWrap;
+pub use unicode_wordwrap::UnicodeWordWrap;
+pub use vertical_metrics::VerticalMetrics;
+pub use white_space_mode::WhiteSpace;
 pub use whitespace_wordwrap::WhiteSpaceWordWrap;
-pub use wordwrap::{Wrap, WrapWithPosition};
+pub use wordwrap::{Wrap, WrapWithMetrics, WrapWithPosition};
 
 #[cfg(doctest)]
 doc_comment::doctest!("../README.md");
@@ -71,14 +115,14 @@ doc_comment::doctest!("../README.md");
 mod tests {
     use std::path::PathBuf;
 
-    use ttf_parser::Face;
+    use ttf_parser::{Face, GlyphId};
 
     use crate::{
         measure::TTFParserMeasure, position::CharPosition, whitespace_wordwrap::WhiteSpaceWordWrap,
         wordwrap::Wrap, Position, WrapWithPosition,
     };
 
-    pub fn read_font<'a>() -> Vec<u8> {
+    pub fn read_font() -> Vec<u8> {
         let font_path: PathBuf = [
             env!("CARGO_MANIFEST_DIR"),
             "test_fonts",
@@ -89,6 +133,14 @@ mod tests {
         std::fs::read(font_path).expect("TTF should exist")
     }
 
+    /// A font with only an 'A', from the `ttf-parser` tests
+    pub fn read_demo_font() -> Vec<u8> {
+        let font_path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "test_fonts", "demo.ttf"]
+            .iter()
+            .collect();
+        std::fs::read(font_path).expect("TTF should exist")
+    }
+
     #[test]
     fn nomicon() {
         let font_data = read_font();
@@ -161,9 +213,14 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'T',
+                index: 0,
+                char_index: 0,
                 line: 0,
                 offset: 0,
-                width: 1222
+                width: 1222,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(56)),
             },)
         ));
 
@@ -176,9 +233,14 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'o',
+                index: 32,
+                char_index: 32,
                 line: 1,
-                offset: 15233,
-                width: 1168
+                offset: 15186,
+                width: 1168,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(83)),
             },)
         ));
 
@@ -191,9 +253,14 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: ';',
+                index: 65,
+                char_index: 65,
                 line: 3,
                 offset: 7313,
-                width: 433
+                width: 433,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(31)),
             },)
         ));
     }
@@ -213,9 +280,14 @@ mod tests {
             token,
             Some(CharPosition::Known(Position {
                 ch: 'w',
+                index: 0,
+                char_index: 0,
                 line: 0,
                 offset: 0,
-                width: 1539
+                width: 1539,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(91)),
             }))
         ));
         let token = positions.next();
@@ -223,9 +295,14 @@ mod tests {
             token,
             Some(CharPosition::Known(Position {
                 ch: 'o',
+                index: 1,
+                char_index: 1,
                 line: 1,
                 offset: 0,
-                width: 1168
+                width: 1168,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(83)),
             }))
         ));
         let token = positions.next();
@@ -233,9 +310,14 @@ mod tests {
             token,
             Some(CharPosition::Known(Position {
                 ch: 'r',
+                index: 2,
+                char_index: 2,
                 line: 2,
                 offset: 0,
-                width: 693
+                width: 693,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(86)),
             }))
         ));
         let token = positions.next();
@@ -243,9 +325,14 @@ mod tests {
             token,
             Some(CharPosition::Known(Position {
                 ch: 'd',
+                index: 3,
+                char_index: 3,
                 line: 3,
                 offset: 0,
-                width: 1155
+                width: 1155,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(72)),
             }))
         ));
         assert!(positions.next().is_none());
@@ -1,6 +1,6 @@
-use std::fmt::Formatter;
+use std::{borrow::Cow, fmt::Formatter};
 
-use crate::token::TokenKind;
+use crate::token::{TokenKind, HYPHEN, SOFT_HYPHEN};
 
 pub trait Lines<T> {
     fn lines<'a>(self, text: &'a str) -> LineIterator<'a, T>;
@@ -11,15 +11,38 @@ where
     T: Iterator<Item = TokenKind>,
 {
     fn lines<'a>(self, text: &'a str) -> LineIterator<'a, T> {
-        LineIterator { text, tokens: self }
+        LineIterator {
+            text,
+            tokens: self,
+            ends_with_hyphen: false,
+        }
     }
 }
 
 /// Provides lines as `&str`
+///
+/// A line that breaks at a soft hyphen ends with the soft hyphen, and one that breaks at a
+/// hyphenation point of a `Hyphenator` ends without a hyphen, see `with_hyphens()`.
 #[derive(Clone, PartialEq)]
 pub struct LineIterator<'a, T> {
     text: &'a str,
     tokens: T,
+
+    /// The last line breaks at a `SoftHyphen` token, a hyphen is drawn at its end
+    ends_with_hyphen: bool,
+}
+
+impl<'a, T> LineIterator<'a, T> {
+    /// The tokens the lines are made of.
+    pub fn tokens(&self) -> &T {
+        &self.tokens
+    }
+
+    /// Provides lines as `Cow<str>` as their positions are drawn, with a hyphen instead of the
+    /// soft hyphen that a line breaks at and without the other soft hyphens.
+    pub fn with_hyphens(self) -> HyphenLineIterator<'a, T> {
+        HyphenLineIterator { lines: self }
+    }
 }
 
 impl<'a, T> std::fmt::Debug for LineIterator<'a, T>
@@ -42,7 +65,7 @@ where
         let mut last_token: Option<TokenKind> = None;
 
         // Take tokens until there is no more space left (or the next token doesn't fit)
-        while let Some(token_kind) = self.tokens.next() {
+        for token_kind in self.tokens.by_ref() {
             if start_token.is_none() {
                 // Keep track of the first token
                 start_token.replace(token_kind);
@@ -57,6 +80,9 @@ where
             }
         }
 
+        self.ends_with_hyphen =
+            matches!(last_token.or(start_token), Some(TokenKind::SoftHyphen(_)));
+
         match (start_token, last_token) {
             // First token is newline, point to an empty str in the text
             (None, None) => None,
@@ -64,21 +90,64 @@ where
             (None, Some(_)) => unreachable!(),
             // Only one token
             (Some(TokenKind::Newline(_)), None) => Some(&self.text[0..0]),
-            (Some(TokenKind::Optional(token)), None) | (Some(TokenKind::Required(token)), None) => {
+            (Some(TokenKind::Optional(token)), None)
+            | (Some(TokenKind::Required(token)), None)
+            | (Some(TokenKind::SoftHyphen(token)), None) => {
                 Some(&self.text[token.start..token.end])
             }
             // Newlines have been stripped out
             (Some(TokenKind::Newline(_)), Some(_)) | (Some(_), Some(TokenKind::Newline(_))) => {
                 unreachable!()
             }
-            // Tokens are Optional or Required
-            (Some(TokenKind::Optional(start_token)), Some(TokenKind::Optional(end_token)))
-            | (Some(TokenKind::Required(start_token)), Some(TokenKind::Required(end_token)))
-            | (Some(TokenKind::Optional(start_token)), Some(TokenKind::Required(end_token)))
-            | (Some(TokenKind::Required(start_token)), Some(TokenKind::Optional(end_token))) => {
-                Some(&self.text[start_token.start..end_token.end])
-            }
+            // Tokens are Optional, Required or SoftHyphen
+            (
+                Some(
+                    TokenKind::Optional(start_token)
+                    | TokenKind::Required(start_token)
+                    | TokenKind::SoftHyphen(start_token),
+                ),
+                Some(
+                    TokenKind::Optional(end_token)
+                    | TokenKind::Required(end_token)
+                    | TokenKind::SoftHyphen(end_token),
+                ),
+            ) => Some(&self.text[start_token.start..end_token.end]),
+        }
+    }
+}
+
+/// Provides lines as `Cow<str>`, lines that break at a soft hyphen end with a hyphen and the soft
+/// hyphens that are not used are removed
+#[derive(Clone)]
+pub struct HyphenLineIterator<'a, T> {
+    lines: LineIterator<'a, T>,
+}
+
+impl<'a, T> std::fmt::Debug for HyphenLineIterator<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HyphenLineIterator").finish()
+    }
+}
+
+impl<'a, T> Iterator for HyphenLineIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        let ends_with_hyphen = self.lines.ends_with_hyphen;
+        if !ends_with_hyphen && !line.contains(SOFT_HYPHEN) {
+            return Some(Cow::Borrowed(line));
         }
+
+        // The soft hyphen token a line breaks at may be a hyphenation point without a U+00AD
+        let mut hyphenated = line.replace(SOFT_HYPHEN, "");
+        if ends_with_hyphen {
+            hyphenated.push(HYPHEN);
+        }
+        Some(Cow::Owned(hyphenated))
     }
 }
 
@@ -247,4 +316,27 @@ mod tests {
 
         assert!(lines.next().is_none());
     }
+
+    #[test]
+    fn hyphens() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "co\u{AD}op\u{AD}er";
+        let lines = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(6000, text, &measure)
+            .add_newlines_at(6000)
+            .lines(text);
+
+        // The line keeps the soft hyphen it breaks at
+        let hyphenated: Vec<&str> = lines.clone().collect();
+        assert_eq!(hyphenated, vec!["co\u{AD}op\u{AD}", "er"]);
+
+        // Which is drawn as a hyphen
+        let hyphenated: Vec<Cow<'_, str>> = lines.with_hyphens().collect();
+        assert_eq!(hyphenated, vec!["coop-", "er"]);
+    }
 }
@@ -1,8 +1,11 @@
 use std::fmt::Formatter;
 
 use crate::{
+    line_widths::LineWidths,
+    metrics::{LineBreak, LineBreaks},
     partial_tokens::{PartialToken, PartialTokens},
     token::{Kind, TokenKind},
+    WhiteSpace,
 };
 
 /// A trait for injecting Synthetic newlines at a display_width.
@@ -16,11 +19,16 @@ where
 {
     fn add_newlines_at(self, max_width: u32) -> LineBreakIterator<T> {
         LineBreakIterator {
-            max_width,
+            line_widths: LineWidths::new(max_width),
+            line: 0,
+            is_first_line: true,
             tokens: self,
             width_remaining: max_width,
             previous_token_kind: None,
             force_newline: false,
+            line_break: LineBreak::Wrap,
+            wrapped_white_space: 0,
+            white_space: None,
         }
     }
 }
@@ -28,8 +36,14 @@ where
 /// Injects Synthetic newlines into the token stream at a given display width.
 #[derive(Clone)]
 pub struct LineBreakIterator<T> {
-    /// Maximum display width for the lines
-    max_width: u32,
+    /// Maximum display width for each line
+    line_widths: LineWidths,
+
+    /// The line we are filling
+    line: u32,
+
+    /// The line we are filling starts a paragraph
+    is_first_line: bool,
 
     /// Tokens used to fill the line
     tokens: T,
@@ -42,16 +56,83 @@ pub struct LineBreakIterator<T> {
     previous_token_kind: Option<Kind>,
 
     force_newline: bool,
+
+    /// Why the last line ended
+    line_break: LineBreak,
+
+    /// The width of the whitespace the last line wrapped at
+    wrapped_white_space: u32,
+
+    /// Whether lines wrap and keep their leading whitespace
+    white_space: Option<WhiteSpace>,
 }
 
 impl<T> LineBreakIterator<T>
 where
     T: PartialTokens<Item = PartialToken>,
 {
-    fn newline(&mut self) {
-        self.width_remaining = self.max_width;
+    /// Uses `line_widths` instead of the same `max_width` for every line.
+    pub fn with_line_widths(mut self, line_widths: LineWidths) -> Self {
+        self.line_widths = line_widths;
+        self.start_line();
+        self
+    }
+
+    /// Wraps lines, and keeps the whitespace that starts a paragraph, as `white_space` does.
+    pub fn with_white_space(mut self, white_space: Option<WhiteSpace>) -> Self {
+        self.white_space = white_space;
+        self.start_line();
+        self
+    }
+
+    /// Starts the next line, a new paragraph if `is_first_line`.
+    fn newline(&mut self, is_first_line: bool) {
+        self.line += 1;
+        self.is_first_line = is_first_line;
+        self.start_line();
         self.previous_token_kind.take();
     }
+
+    /// Resets the space remaining to the width of the current line.
+    fn start_line(&mut self) {
+        self.width_remaining = self.max_width();
+        self.tokens.set_max_width(self.width_remaining);
+    }
+
+    /// The width of the current line
+    fn max_width(&self) -> u32 {
+        if !self.wraps() {
+            return u32::MAX;
+        }
+        self.line_widths.width(self.line, self.is_first_line)
+    }
+
+    /// The width of the next line, if this line is wrapped
+    fn next_max_width(&self) -> u32 {
+        if !self.wraps() {
+            return u32::MAX;
+        }
+        self.line_widths.width(self.line + 1, false)
+    }
+
+    /// Whether lines are wrapped at their widths
+    fn wraps(&self) -> bool {
+        self.white_space.is_none_or(WhiteSpace::wraps)
+    }
+
+    /// Whether the whitespace at the start of the current line is kept
+    fn keeps_leading_white_space(&self) -> bool {
+        self.is_first_line
+            && self
+                .white_space
+                .is_some_and(WhiteSpace::keeps_leading_white_space)
+    }
+
+    /// Whether the whitespace at the end of the text is kept
+    fn keeps_trailing_white_space(&self) -> bool {
+        self.white_space
+            .is_some_and(WhiteSpace::keeps_trailing_white_space)
+    }
 }
 
 impl<T> std::fmt::Debug for LineBreakIterator<T>
@@ -61,7 +142,7 @@ where
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TokenWrapIterator")
-            .field("max_width", &self.max_width)
+            .field("line_widths", &self.line_widths)
             .finish()
     }
 }
@@ -73,8 +154,11 @@ where
     type Item = TokenKind;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.wrapped_white_space = 0;
+
         if self.force_newline {
             self.force_newline = false;
+            self.newline(false);
             return Some(TokenKind::Newline(None));
         }
 
@@ -83,14 +167,18 @@ where
             return match partial_token {
                 PartialToken::TokenOverflow(token_kind) => {
                     // Only force the newline if there is a token following this one
-                    if self.tokens.peek(self.max_width).is_some() {
+                    if self.tokens.peek(self.next_max_width()).is_some() {
                         self.force_newline = true;
+                        self.line_break = LineBreak::Forced;
                     }
                     Some(token_kind)
                 }
                 PartialToken::Token(token_kind) => {
-                    // Skip optional tokens at the beginning of a line
-                    if self.previous_token_kind.is_none() && token_kind.is_optional() {
+                    // Skip optional tokens and soft hyphens at the beginning of a line
+                    if self.previous_token_kind.is_none()
+                        && ((token_kind.is_optional() && !self.keeps_leading_white_space())
+                            || token_kind.is_soft_hyphen())
+                    {
                         continue;
                     }
 
@@ -101,23 +189,57 @@ where
                             let width_remaining = self.width_remaining - token.display_width;
                             match self.tokens.peek(width_remaining) {
                                 Some(PartialToken::Token(_)) => TokenKind::Optional(token),
+                                None if self.keeps_trailing_white_space() => {
+                                    TokenKind::Optional(token)
+                                }
                                 None
                                 | Some(PartialToken::TokenOverflow(_))
                                 | Some(PartialToken::EndOfLine) => {
                                     // The following token will not fit on the line, do not keep the current
                                     // optional token, replace it with a synthetic newline
+                                    self.line_break = LineBreak::Wrap;
+                                    self.wrapped_white_space = token.display_width;
                                     TokenKind::Newline(None)
                                 }
                             }
                         }
+                        TokenKind::SoftHyphen(token) => {
+                            // Only return the SoftHyphen token_kind if the line breaks after it
+                            let next_line = self.tokens.peek(self.next_max_width());
+                            match self.tokens.peek(self.width_remaining) {
+                                None => continue,
+                                Some(partial_token @ PartialToken::Token(_))
+                                    if partial_token == next_line.unwrap() =>
+                                {
+                                    // The following token fits on the line as well as it would
+                                    // on the next one, the soft hyphen is not drawn
+                                    continue;
+                                }
+                                Some(PartialToken::Token(_))
+                                | Some(PartialToken::TokenOverflow(_))
+                                | Some(PartialToken::EndOfLine) => {
+                                    if token.display_width <= self.width_remaining {
+                                        // Draw the hyphen, then break the line
+                                        self.force_newline = true;
+                                        self.line_break = LineBreak::Hyphen;
+                                        TokenKind::SoftHyphen(token)
+                                    } else {
+                                        // There is no room for the hyphen, break without it
+                                        self.line_break = LineBreak::Wrap;
+                                        TokenKind::Newline(None)
+                                    }
+                                }
+                            }
+                        }
                         TokenKind::Newline(token) => {
                             // All types of newlines pass through
+                            self.line_break = LineBreak::Newline;
                             TokenKind::Newline(token)
                         }
                     };
 
                     if token_kind.is_newline() {
-                        self.newline();
+                        self.newline(matches!(token_kind, TokenKind::Newline(Some(_))));
                     } else {
                         // token accepted, no longer at the start of a line
                         self.width_remaining -= token_kind.width();
@@ -127,7 +249,13 @@ where
                     Some(token_kind)
                 }
                 PartialToken::EndOfLine => {
-                    self.newline();
+                    // Breaking within a token is forced, there was nowhere else to break
+                    self.line_break = if self.tokens.is_split() {
+                        LineBreak::Forced
+                    } else {
+                        LineBreak::Wrap
+                    };
+                    self.newline(false);
                     Some(TokenKind::Newline(None))
                 }
             };
@@ -137,13 +265,24 @@ where
     }
 }
 
+impl<T> LineBreaks for LineBreakIterator<T> {
+    fn line_break(&self) -> LineBreak {
+        self.line_break
+    }
+
+    fn wrapped_white_space(&self) -> u32 {
+        self.wrapped_white_space
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ttf_parser::Face;
 
     use crate::{
-        grapheme_width::WithGraphemeWidth, partial_tokens::WithPartialTokens, token::Token,
-        whitespace::TokenizeWhiteSpace, TTFParserMeasure,
+        grapheme_width::WithGraphemeWidth, line_widths::LineWidths,
+        partial_tokens::WithPartialTokens, token::Token, whitespace::TokenizeWhiteSpace, Measure,
+        TTFParserMeasure,
     };
 
     use super::*;
@@ -205,6 +344,59 @@ mod tests {
         assert!(tokens.next().is_none());
     }
 
+    #[test]
+    fn line_widths() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "1234567890 1234567890";
+        let tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(12000, text, &measure)
+            .add_newlines_at(12000)
+            .with_line_widths(
+                LineWidths::new(12000)
+                    .with_widths(3000, |line| [6000, 3000].get(line as usize).copied()),
+            );
+
+        // The first two lines are narrower
+        let lines: Vec<&str> = tokens
+            .filter_map(|token_kind| token_kind.into_token())
+            .map(|token| token.as_str(text))
+            .collect();
+        assert_eq!(lines, vec!["12345", "67", "890", "1234567890"]);
+    }
+
+    #[test]
+    fn endless_line_widths() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // Every line has its own width, the widths alternate without end
+        let text = "1234567890 1234567890";
+        let tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(12000, text, &measure)
+            .add_newlines_at(12000)
+            .with_line_widths(
+                LineWidths::new(12000)
+                    .with_widths(3000, |line| Some(if line % 2 == 0 { 6000 } else { 3000 })),
+            );
+
+        let lines: Vec<&str> = tokens
+            .filter_map(|token_kind| token_kind.into_token())
+            .map(|token| token.as_str(text))
+            .collect();
+        assert_eq!(
+            lines,
+            vec!["12345", "67", "890", " ", "1", "23", "45678", "90"]
+        );
+    }
+
     #[test]
     fn synthetic_newlines() {
         let font_data = crate::tests::read_font();
@@ -347,4 +539,95 @@ mod tests {
 
         assert!(tokens.next().is_none());
     }
+
+    #[test]
+    fn soft_hyphens() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "co\u{AD}op\u{AD}er";
+        let mut tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(6000, text, &measure)
+            .add_newlines_at(6000);
+
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "co");
+
+        // The first soft hyphen is not used
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "op");
+
+        // The line breaks at the second one
+        assert!(matches!(tokens.next(), Some(TokenKind::SoftHyphen(_))));
+        assert!(matches!(tokens.next(), Some(TokenKind::Newline(None))));
+
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "er");
+
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn no_room_for_hyphen() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // "coop" fits, but "coop-" does not, so the line breaks at the first soft hyphen
+        let text = "co\u{AD}op\u{AD}er";
+        let mut tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(5000, text, &measure)
+            .add_newlines_at(5000);
+
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "co");
+
+        assert!(matches!(tokens.next(), Some(TokenKind::SoftHyphen(_))));
+        assert!(matches!(tokens.next(), Some(TokenKind::Newline(None))));
+
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "op");
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "er");
+
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn overflow_starts_a_line() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // "W" is wider than the first line, the line after it is wider
+        let max_width = measure.str("i i");
+        let first_width = measure.str("i");
+        let line_widths = LineWidths::new(max_width)
+            .with_widths(first_width, move |line| (line == 0).then_some(first_width));
+        let text = "W i i i";
+        let tokens: Vec<TokenKind> = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(max_width, text, &measure)
+            .add_newlines_at(max_width)
+            .with_line_widths(line_widths)
+            .collect();
+
+        // The line after the overflowing token starts like any other, without the space and at
+        // the width of the second line
+        let lines: Vec<Vec<&str>> = tokens
+            .split(|token_kind| token_kind.is_newline())
+            .map(|line| {
+                line.iter()
+                    .map(|token_kind| token_kind.into_token().unwrap().as_str(text))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(lines, vec![vec!["W"], vec!["i", " ", "i"], vec!["i"]]);
+    }
 }
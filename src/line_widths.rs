@@ -0,0 +1,90 @@
+use std::{fmt::Formatter, rc::Rc};
+
+/// The width of a line by its number, `None` for a line that is `max_width` wide
+type WidthFn = dyn Fn(u32) -> Option<u32>;
+
+/// The width available to each line.
+///
+/// Lines without their own width are `max_width` wide. Indents are taken from the width of the
+/// lines they apply to.
+#[derive(Clone)]
+pub struct LineWidths {
+    max_width: u32,
+    widths: Option<Rc<WidthFn>>,
+
+    /// The width of the narrowest line, before indents
+    narrowest: u32,
+    first_line_indent: u32,
+    hanging_indent: u32,
+}
+
+impl LineWidths {
+    /// Creates `LineWidths` where every line is `max_width` wide.
+    pub fn new(max_width: u32) -> Self {
+        Self {
+            max_width,
+            widths: None,
+            narrowest: max_width,
+            first_line_indent: 0,
+            hanging_indent: 0,
+        }
+    }
+
+    /// Looks up the width of each line by its number with `widths`, which is only called for the
+    /// lines that are filled. No line is narrower than `narrowest`.
+    pub fn with_widths<F>(mut self, narrowest: u32, widths: F) -> Self
+    where
+        F: Fn(u32) -> Option<u32> + 'static,
+    {
+        self.widths = Some(Rc::new(widths));
+        self.narrowest = narrowest;
+        self
+    }
+
+    /// Indents the first line of each paragraph by `indent`.
+    pub fn with_first_line_indent(mut self, indent: u32) -> Self {
+        self.first_line_indent = indent;
+        self
+    }
+
+    /// Indents every line but the first of each paragraph by `indent`.
+    pub fn with_hanging_indent(mut self, indent: u32) -> Self {
+        self.hanging_indent = indent;
+        self
+    }
+
+    /// The indent of a line, `is_first_line` if it starts a paragraph.
+    pub fn indent(&self, is_first_line: bool) -> u32 {
+        if is_first_line {
+            self.first_line_indent
+        } else {
+            self.hanging_indent
+        }
+    }
+
+    /// The width available to `line`, after its indent.
+    pub fn width(&self, line: u32, is_first_line: bool) -> u32 {
+        self.widths
+            .as_ref()
+            .and_then(|widths| widths(line))
+            .unwrap_or(self.max_width)
+            .saturating_sub(self.indent(is_first_line))
+    }
+
+    /// The width of the narrowest line, after the widest indent.
+    pub fn narrowest(&self) -> u32 {
+        let indent = self.first_line_indent.max(self.hanging_indent);
+        self.narrowest.min(self.max_width).saturating_sub(indent)
+    }
+}
+
+impl std::fmt::Debug for LineWidths {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LineWidths")
+            .field("max_width", &self.max_width)
+            .field("narrowest", &self.narrowest)
+            .field("first_line_indent", &self.first_line_indent)
+            .field("hanging_indent", &self.hanging_indent)
+            .finish()
+    }
+}
@@ -1,6 +1,9 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::RwLock};
 
-use ttf_parser::Face;
+use ttf_parser::{
+    gpos::{PairAdjustment, PositioningSubtable},
+    Face, GlyphId, Tag,
+};
 
 /// Implementing this allows overriding of how glyphs are measured.
 pub trait Measure: std::fmt::Debug {
@@ -11,43 +14,284 @@ pub trait Measure: std::fmt::Debug {
     ///
     /// Returns `None` if the width is not known.
     fn char(&self, c: char) -> Option<u16>;
+
+    /// The adjustment to the width of `left` when it is followed by `right`, e.g. pair kerning.
+    ///
+    /// Implementations that adjust pairs should include the adjustment in `str()`, between chars
+    /// of the same word or the same run of whitespace.
+    fn kerning(&self, _left: char, _right: char) -> i16 {
+        0
+    }
+
+    /// Measures `text`, which starts at byte `index` of the text being wrapped.
+    fn str_at(&self, _index: usize, text: &str) -> u32 {
+        self.str(text)
+    }
+
+    /// Measures `c`, which is at byte `index` of the text being wrapped.
+    ///
+    /// Implementations that measure parts of the text differently, such as `SpanMeasure`, use
+    /// `index` to find the part.
+    fn char_at(&self, _index: usize, c: char) -> Option<u16> {
+        self.char(c)
+    }
+
+    /// The kerning between `left` and `right`, which is at byte `index` of the text being wrapped.
+    fn kerning_at(&self, _index: usize, left: char, right: char) -> i16 {
+        self.kerning(left, right)
+    }
+
+    /// The span that byte `index` of the text being wrapped is in, see `SpanMeasure`.
+    fn span_at(&self, _index: usize) -> Option<usize> {
+        None
+    }
+
+    /// The face that measures `c`, at byte `index` of the text being wrapped, see
+    /// `FallbackMeasure`.
+    fn face_at(&self, _index: usize, _c: char) -> Option<usize> {
+        None
+    }
+
+    /// The glyph of `c` in the font, so that it does not need to be looked up again to draw it.
+    ///
+    /// Returns `None` if the glyph is not known.
+    fn glyph(&self, _c: char) -> Option<GlyphId> {
+        None
+    }
+
+    /// The glyph of `c`, which is at byte `index` of the text being wrapped.
+    fn glyph_at(&self, _index: usize, c: char) -> Option<GlyphId> {
+        self.glyph(c)
+    }
+
+    /// Both `char_at()` and `glyph_at()`, for implementations that find them with one lookup.
+    fn char_glyph_at(&self, index: usize, c: char) -> (Option<u16>, Option<GlyphId>) {
+        (self.char_at(index, c), self.glyph_at(index, c))
+    }
+}
+
+/// Whether kerning applies between `left` and `right`.
+///
+/// Tokens are only kerned within, so pairs where a word meets whitespace or a soft hyphen are not
+/// kerned. `Measure::str()` of a line is then the sum of the widths of its tokens.
+pub(crate) fn is_kerned(left: char, right: char) -> bool {
+    const SOFT_HYPHEN: char = '\u{AD}';
+    left.is_whitespace() == right.is_whitespace() && left != SOFT_HYPHEN && right != SOFT_HYPHEN
+}
+
+/// The last of the chars that `TTFParserMeasure` looks up when it is created.
+const LATIN_1: u32 = 0xFF;
+
+/// The glyph of a char and its advance width.
+#[derive(Copy, Clone, Debug)]
+struct CachedGlyph {
+    glyph_id: Option<GlyphId>,
+    width: Option<u16>,
 }
 
 /// Implements measuring glyphs via `ttf_parser`
-#[derive(Clone, Debug)]
+///
+/// The glyphs of ASCII and Latin-1 chars are looked up when it is created, other chars are
+/// cached as they are measured. It can be shared between threads.
+#[derive(Debug)]
 pub struct TTFParserMeasure<'a> {
     face: &'a Face<'a>,
-    cache: HashMap<char, u16>,
+
+    /// The glyphs of the chars up to U+00FF
+    latin1: Box<[CachedGlyph]>,
+
+    /// The glyphs of the other chars that have been measured
+    cache: RwLock<HashMap<char, CachedGlyph>>,
+
+    /// The kerning of the pairs of glyphs that have been measured
+    kerning: RwLock<HashMap<(GlyphId, GlyphId), i16>>,
 }
 
 impl<'a> TTFParserMeasure<'a> {
     /// Creates a new TTFParserMeasure for the font `face`.
     pub fn new(face: &'a Face<'a>) -> Self {
+        let latin1 = (0..=LATIN_1)
+            .filter_map(char::from_u32)
+            .map(|c| Self::lookup(face, c))
+            .collect();
+
         Self {
             face,
-            cache: HashMap::new(),
+            latin1,
+            cache: RwLock::new(HashMap::new()),
+            kerning: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Looks up the glyphs of `chars` ahead of time, e.g. the characters of a language.
+    pub fn cache_chars<I>(&self, chars: I)
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let mut cache = self
+            .cache
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        for c in chars.into_iter().filter(|&c| u32::from(c) > LATIN_1) {
+            cache.entry(c).or_insert_with(|| Self::lookup(self.face, c));
+        }
+    }
+
+    /// Looks up the glyph of `c` in the tables of `face`.
+    fn lookup(face: &Face<'_>, c: char) -> CachedGlyph {
+        let glyph_id = face.glyph_index(c);
+        CachedGlyph {
+            glyph_id,
+            width: glyph_id.and_then(|glyph_id| face.glyph_hor_advance(glyph_id)),
+        }
+    }
+
+    /// The glyph of `c`, from the cache if it has been looked up before.
+    fn cached(&self, c: char) -> CachedGlyph {
+        if let Some(glyph) = self.latin1.get(c as usize) {
+            return *glyph;
+        }
+
+        let cache = self.cache.read().unwrap_or_else(|error| error.into_inner());
+        if let Some(glyph) = cache.get(&c) {
+            return *glyph;
+        }
+        drop(cache);
+
+        let glyph = Self::lookup(self.face, c);
+        self.cache
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+            .insert(c, glyph);
+        glyph
+    }
+}
+
+impl<'a> Clone for TTFParserMeasure<'a> {
+    fn clone(&self) -> Self {
+        let cache = self.cache.read().unwrap_or_else(|error| error.into_inner());
+        let kerning = self
+            .kerning
+            .read()
+            .unwrap_or_else(|error| error.into_inner());
+        Self {
+            face: self.face,
+            latin1: self.latin1.clone(),
+            cache: RwLock::new(cache.clone()),
+            kerning: RwLock::new(kerning.clone()),
+        }
+    }
+}
+
+impl<'a> TTFParserMeasure<'a> {
+    /// The kerning of a pair of glyphs, from the cache if it has been looked up before.
+    fn cached_kerning(&self, left: GlyphId, right: GlyphId) -> i16 {
+        let cache = self
+            .kerning
+            .read()
+            .unwrap_or_else(|error| error.into_inner());
+        if let Some(&kerning) = cache.get(&(left, right)) {
+            return kerning;
         }
+        drop(cache);
+
+        let kerning = self
+            .gpos_kerning(left, right)
+            .or_else(|| self.kern_kerning(left, right))
+            .unwrap_or_default();
+        self.kerning
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+            .insert((left, right), kerning);
+        kerning
+    }
+
+    /// Looks up the pair adjustment in the `kern` feature of the `GPOS` table.
+    fn gpos_kerning(&self, left: GlyphId, right: GlyphId) -> Option<i16> {
+        let gpos = self.face.tables().gpos?;
+
+        gpos.features
+            .into_iter()
+            .filter(|feature| feature.tag == Tag::from_bytes(b"kern"))
+            .flat_map(|feature| feature.lookup_indices)
+            .filter_map(|index| gpos.lookups.get(index))
+            .flat_map(|lookup| lookup.subtables.into_iter::<PositioningSubtable<'_>>())
+            .find_map(|subtable| match subtable {
+                PositioningSubtable::Pair(PairAdjustment::Format1 { coverage, sets }) => {
+                    let set = sets.get(coverage.get(left)?)?;
+                    set.get(right).map(|(value, _)| value.x_advance)
+                }
+                PositioningSubtable::Pair(PairAdjustment::Format2 {
+                    coverage,
+                    classes,
+                    matrix,
+                }) => {
+                    if !coverage.contains(left) {
+                        return None;
+                    }
+                    let classes = (classes.0.get(left), classes.1.get(right));
+                    matrix.get(classes).map(|(value, _)| value.x_advance)
+                }
+                _ => None,
+            })
+    }
+
+    /// Looks up the pair in the legacy `kern` table.
+    fn kern_kerning(&self, left: GlyphId, right: GlyphId) -> Option<i16> {
+        let kern = self.face.tables().kern?;
+
+        kern.subtables
+            .into_iter()
+            .filter(|subtable| {
+                subtable.horizontal && !subtable.variable && !subtable.has_cross_stream
+            })
+            .find_map(|subtable| subtable.glyphs_kerning(left, right))
     }
 }
 
 impl<'a> Measure for TTFParserMeasure<'a> {
     fn str(&self, text: &str) -> u32 {
-        text.chars()
-            .map(|c| u32::from(self.char(c).unwrap_or_default()))
-            .sum()
+        let mut previous = None;
+
+        text.chars().fold(0, |width: u32, c| {
+            let kerning = previous
+                .replace(c)
+                .filter(|&left| is_kerned(left, c))
+                .map_or(0, |left| self.kerning(left, c));
+            width
+                .saturating_add(u32::from(self.char(c).unwrap_or_default()))
+                .saturating_add_signed(i32::from(kerning))
+        })
     }
 
     #[inline]
     fn char(&self, c: char) -> Option<u16> {
-        self.face
-            .glyph_index(c)
-            .map(|glyph_id| self.face.glyph_hor_advance(glyph_id))
-            .flatten()
+        self.cached(c).width
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        let glyphs = (self.cached(left).glyph_id, self.cached(right).glyph_id);
+
+        match glyphs {
+            (Some(left), Some(right)) => self.cached_kerning(left, right),
+            _ => 0,
+        }
+    }
+
+    fn glyph(&self, c: char) -> Option<GlyphId> {
+        self.cached(c).glyph_id
+    }
+
+    fn char_glyph_at(&self, _index: usize, c: char) -> (Option<u16>, Option<GlyphId>) {
+        let glyph = self.cached(c);
+        (glyph.width, glyph.glyph_id)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{WhiteSpaceWordWrap, WrapWithMetrics};
+
     use super::*;
 
     #[test]
@@ -80,6 +324,102 @@ mod tests {
 
         let text = "caverns are not for the";
         let width = dw.str(text);
-        assert_eq!(width, 20483);
+        assert_eq!(width, 20436);
+    }
+
+    #[test]
+    fn kerning() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let dw = TTFParserMeasure::new(&font_face);
+
+        // Roboto kerns with the GPOS table
+        assert_eq!(dw.kerning('T', 'o'), -99);
+        assert_eq!(dw.kerning('o', 'T'), 0);
+
+        let width = dw.str("To");
+        assert_eq!(
+            width,
+            u32::from(dw.char('T').unwrap()) + u32::from(dw.char('o').unwrap()) - 99
+        );
+
+        // Pairs are looked up once
+        assert_eq!(dw.kerning.read().unwrap().len(), 2);
+        dw.str("ToTo");
+        assert_eq!(dw.kerning.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn kerning_within_words() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let dw = TTFParserMeasure::new(&font_face);
+
+        // "T" kerns with a space, but tokens are only kerned within
+        assert_eq!(dw.kerning('T', ' '), -40);
+        assert_eq!(dw.str("To To"), 2 * dw.str("To") + dw.str(" "));
+
+        // So a line is as wide as the wrapper measures it
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &dw);
+        let widths: Vec<u32> = "To To"
+            .wrap_with_metrics(&word_wrap)
+            .map(|line| line.width)
+            .collect();
+        assert_eq!(widths, vec![dw.str("To To")]);
+    }
+
+    #[test]
+    fn cache() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let dw = TTFParserMeasure::new(&font_face);
+
+        // Latin-1 is looked up up front, other chars when they are measured
+        assert_eq!(dw.char('é'), Some(1085));
+        assert!(dw.cache.read().unwrap().is_empty());
+
+        let width = font_face.glyph_hor_advance(font_face.glyph_index('Ω').unwrap());
+        assert_eq!(dw.char('Ω'), width);
+        assert_eq!(dw.char('Ω'), width);
+        assert_eq!(dw.char('今'), None);
+        assert_eq!(dw.cache.read().unwrap().len(), 2);
+
+        // The cache can be filled ahead of time
+        dw.cache_chars("αβγ ab".chars());
+        assert_eq!(dw.cache.read().unwrap().len(), 5);
+        assert_eq!(dw.glyph('β'), font_face.glyph_index('β'));
+
+        // The width and glyph come from the same entry
+        assert_eq!(
+            dw.char_glyph_at(0, 'Ω'),
+            (width, font_face.glyph_index('Ω'))
+        );
+        assert_eq!(dw.char_glyph_at(0, '今'), (None, None));
+        assert_eq!(dw.cache.read().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn threads() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let dw = TTFParserMeasure::new(&font_face);
+
+        // The cache is shared by each thread that measures with it
+        let widths: Vec<u32> = std::thread::scope(|scope| {
+            let threads: Vec<_> = ["αβγ", "γδε", "aoeu"]
+                .iter()
+                .map(|text| {
+                    let dw = &dw;
+                    scope.spawn(move || dw.str(text))
+                })
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect()
+        });
+
+        assert_eq!(widths[2], 4496);
+        assert_eq!(dw.cache.read().unwrap().len(), 5);
     }
 }
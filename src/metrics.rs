@@ -0,0 +1,350 @@
+use std::{fmt::Formatter, ops::Range};
+
+use crate::{token::TokenKind, VerticalMetrics};
+
+/// Why a line ended.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineBreak {
+    /// The text has a newline.
+    Newline,
+
+    /// The line was wrapped at a break opportunity, such as whitespace.
+    Wrap,
+
+    /// The line was wrapped at a soft hyphen or hyphenation point, and ends with a hyphen.
+    Hyphen,
+
+    /// A word that is wider than the line was broken.
+    Forced,
+
+    /// The text ended.
+    EndOfText,
+}
+
+/// The extent of a line of text
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LineMetrics {
+    /// The bytes of the text on the line, without the newline
+    pub range: Range<usize>,
+
+    /// The width of the line, including trailing whitespace and a hyphen
+    pub width: u32,
+
+    /// The width of the whitespace at the end of the line, including the whitespace that a wrapped
+    /// line breaks at, which is not drawn
+    pub trailing_white_space: u32,
+
+    /// Why the line ended
+    pub line_break: LineBreak,
+
+    /// The `y` of the baseline, if the lines were placed with `VerticalMetrics`
+    pub baseline: Option<i32>,
+
+    /// Distance from the baseline to the top of the line, if known
+    pub ascent: Option<i16>,
+
+    /// Distance from the baseline to the bottom of the line, usually negative, if known
+    pub descent: Option<i16>,
+}
+
+/// A token stream that knows why it added its synthetic newlines.
+pub trait LineBreaks {
+    /// Why the line that ended with the last synthetic newline was broken.
+    fn line_break(&self) -> LineBreak;
+
+    /// The width of the whitespace that was replaced by the last synthetic newline.
+    fn wrapped_white_space(&self) -> u32;
+}
+
+pub trait Metrics<T> {
+    fn metrics(self) -> MetricsIterator<T>;
+}
+
+impl<T> Metrics<T> for T
+where
+    T: Iterator<Item = TokenKind> + LineBreaks,
+{
+    fn metrics(self) -> MetricsIterator<T> {
+        MetricsIterator {
+            index: 0,
+            line: 0,
+            vertical_metrics: None,
+            tokens: self,
+        }
+    }
+}
+
+/// Provides `LineMetrics` for each line
+#[derive(Clone)]
+pub struct MetricsIterator<T> {
+    /// Where the previous token ended
+    index: usize,
+    line: u32,
+    vertical_metrics: Option<VerticalMetrics>,
+    tokens: T,
+}
+
+impl<T> std::fmt::Debug for MetricsIterator<T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsIterator").finish()
+    }
+}
+
+impl<T> MetricsIterator<T> {
+    /// Places the lines vertically using `vertical_metrics`, if any.
+    pub fn with_vertical_metrics(mut self, vertical_metrics: Option<VerticalMetrics>) -> Self {
+        self.vertical_metrics = vertical_metrics;
+        self
+    }
+}
+
+impl<T> Iterator for MetricsIterator<T>
+where
+    T: Iterator<Item = TokenKind> + LineBreaks,
+{
+    type Item = LineMetrics;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut range: Option<Range<usize>> = None;
+        let mut width: u32 = 0;
+        let mut trailing_white_space: u32 = 0;
+
+        let line_break = loop {
+            match self.tokens.next() {
+                // No more tokens, the text has ended
+                None if range.is_none() => return None,
+                None => break LineBreak::EndOfText,
+                Some(TokenKind::Newline(Some(token))) => {
+                    // An empty line sits at the newline
+                    range.get_or_insert(token.start..token.start);
+                    self.index = token.end;
+                    break LineBreak::Newline;
+                }
+                Some(TokenKind::Newline(None)) => {
+                    // The whitespace the line wrapped at is at the end of it
+                    let wrapped_white_space = self.tokens.wrapped_white_space();
+                    width += wrapped_white_space;
+                    trailing_white_space += wrapped_white_space;
+                    break self.tokens.line_break();
+                }
+                Some(token_kind) => {
+                    let token = token_kind.into_token().unwrap();
+                    let start = range.as_ref().map_or(token.start, |range| range.start);
+                    range.replace(start..token.end);
+                    self.index = token.end;
+
+                    width += token_kind.width();
+                    if token_kind.is_optional() {
+                        trailing_white_space += token_kind.width();
+                    } else {
+                        trailing_white_space = 0;
+                    }
+                }
+            }
+        };
+
+        let line = self.line;
+        self.line += 1;
+
+        Some(LineMetrics {
+            range: range.unwrap_or(self.index..self.index),
+            width,
+            trailing_white_space,
+            line_break,
+            baseline: self.vertical_metrics.map(|metrics| metrics.baseline(line)),
+            ascent: self.vertical_metrics.map(|metrics| metrics.ascent),
+            descent: self.vertical_metrics.map(|metrics| metrics.descent),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{
+        Measure, OptimalWordWrap, TTFParserMeasure, UnicodeWordWrap, WhiteSpaceWordWrap,
+        WrapWithMetrics,
+    };
+
+    use super::*;
+
+    #[test]
+    fn line_breaks() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(6000, &measure);
+
+        let text = "12 3\n\nco\u{AD}op\u{AD}er\n1234567890 1";
+        let metrics: Vec<LineMetrics> = text.wrap_with_metrics(&word_wrap).collect();
+
+        let lines: Vec<(&str, LineBreak)> = metrics
+            .iter()
+            .map(|metrics| (&text[metrics.range.clone()], metrics.line_break))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("12 3", LineBreak::Newline),
+                ("", LineBreak::Newline),
+                ("co\u{AD}op\u{AD}", LineBreak::Hyphen),
+                ("er", LineBreak::Newline),
+                ("12345", LineBreak::Forced),
+                ("67890", LineBreak::Wrap),
+                ("1", LineBreak::EndOfText),
+            ]
+        );
+
+        // The empty line is at its newline
+        assert_eq!(metrics[1].range, 5..5);
+    }
+
+    #[test]
+    fn vertical() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let vertical_metrics = VerticalMetrics::from_face(&font_face).with_line_height(1.5);
+
+        let word_wrap =
+            WhiteSpaceWordWrap::new(20000, &measure).with_vertical_metrics(vertical_metrics);
+
+        let text = "Mary had a little lamb whose fleece was white as snow";
+        let metrics: Vec<LineMetrics> = text.wrap_with_metrics(&word_wrap).collect();
+
+        let baselines: Vec<Option<i32>> = metrics.iter().map(|line| line.baseline).collect();
+        assert_eq!(baselines, vec![Some(2500), Some(6100), Some(9700)]);
+        assert!(metrics
+            .iter()
+            .all(|line| line.ascent == Some(1900) && line.descent == Some(-500)));
+    }
+
+    #[test]
+    fn widths() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &measure);
+
+        let text = "caverns are  \nnot for the";
+        let metrics: Vec<LineMetrics> = text.wrap_with_metrics(&word_wrap).collect();
+
+        assert_eq!(
+            metrics[0],
+            LineMetrics {
+                range: 0..13,
+                width: measure.str("caverns") + measure.str(" ") * 3 + measure.str("are"),
+                trailing_white_space: measure.str(" ") * 2,
+                line_break: LineBreak::Newline,
+                baseline: None,
+                ascent: None,
+                descent: None,
+            }
+        );
+
+        // The hyphen is part of the width
+        let word_wrap = WhiteSpaceWordWrap::new(6000, &measure);
+        let metrics: Vec<LineMetrics> =
+            "co\u{AD}op\u{AD}er".wrap_with_metrics(&word_wrap).collect();
+        assert_eq!(
+            metrics[0].width,
+            measure.str("co") + measure.str("op") + measure.str("-")
+        );
+    }
+
+    #[test]
+    fn wrapped_white_space() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // The lines wrap at two spaces, which are not drawn but end the first line
+        let text = "lamb  whose";
+        let max_width = measure.str("lamb  wh");
+        let expected = |metrics: &LineMetrics| {
+            (
+                &text[metrics.range.clone()],
+                metrics.width,
+                metrics.trailing_white_space,
+            )
+        };
+        let spaces = measure.str("  ");
+
+        let word_wrap = WhiteSpaceWordWrap::new(max_width, &measure);
+        let metrics: Vec<LineMetrics> = text.wrap_with_metrics(&word_wrap).collect();
+        assert_eq!(
+            metrics.iter().map(expected).collect::<Vec<_>>(),
+            vec![
+                ("lamb", measure.str("lamb") + spaces, spaces),
+                ("whose", measure.str("whose"), 0),
+            ]
+        );
+
+        let word_wrap = OptimalWordWrap::new(max_width, &measure);
+        let metrics: Vec<LineMetrics> = text.wrap_with_metrics(&word_wrap).collect();
+        assert_eq!(
+            metrics.iter().map(expected).collect::<Vec<_>>(),
+            vec![
+                ("lamb", measure.str("lamb") + spaces, spaces),
+                ("whose", measure.str("whose"), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn unicode() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = UnicodeWordWrap::new(5000, &measure);
+
+        // Breaking after the hyphen is not forced
+        let text = "well-to-do";
+        let line_breaks: Vec<LineBreak> = text
+            .wrap_with_metrics(&word_wrap)
+            .map(|metrics| metrics.line_break)
+            .collect();
+        assert_eq!(line_breaks, vec![LineBreak::Wrap, LineBreak::EndOfText]);
+    }
+
+    #[test]
+    fn optimal() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = OptimalWordWrap::new(6000, &measure);
+
+        let text = "12 3\n1234567890";
+        let lines: Vec<(&str, LineBreak)> = text
+            .wrap_with_metrics(&word_wrap)
+            .map(|metrics| (&text[metrics.range], metrics.line_break))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("12 3", LineBreak::Newline),
+                ("12345", LineBreak::Forced),
+                ("67890", LineBreak::EndOfText),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(6000, &measure);
+
+        assert!("".wrap_with_metrics(&word_wrap).next().is_none());
+    }
+}
@@ -0,0 +1,485 @@
+use std::{collections::VecDeque, fmt::Formatter, iter::Peekable};
+
+use crate::{
+    metrics::{LineBreak, LineBreaks},
+    token::{Token, TokenKind},
+    Measure,
+};
+
+/// Demerits added to every line, so that fewer lines are preferred.
+const LINE_PENALTY: f64 = 10.0;
+
+/// Penalty for breaking a line at a soft hyphen, so that breaks between words are preferred.
+const HYPHEN_PENALTY: f64 = 50.0;
+
+/// Splits `token` into pieces that are no wider than `max_width`.
+///
+/// A grapheme that is wider than `max_width` becomes a piece of its own.
+fn split(
+    mut token: Token,
+    max_width: u32,
+    text: &str,
+    measure: &dyn Measure,
+    items: &mut Vec<TokenKind>,
+) {
+    while token.display_width > max_width {
+        let (head, tail) = match token.split_at_width(max_width, text, measure) {
+            (None, Some(_)) => token.split_at_grapheme(1, text, measure),
+            split => split,
+        };
+
+        if let Some(head) = head {
+            items.push(TokenKind::Required(head));
+        }
+
+        match tail {
+            Some(tail) => token = tail,
+            None => return,
+        }
+    }
+
+    items.push(TokenKind::Required(token));
+}
+
+/// A trait for injecting synthetic newlines that minimize the badness of whole paragraphs.
+pub trait AddOptimalNewlines<T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn add_optimal_newlines_at<'a>(
+        self,
+        max_width: u32,
+        text: &'a str,
+        measure: &'a dyn Measure,
+    ) -> OptimalLineBreakIterator<'a, T>;
+}
+
+impl<T> AddOptimalNewlines<T> for T
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn add_optimal_newlines_at<'a>(
+        self,
+        max_width: u32,
+        text: &'a str,
+        measure: &'a dyn Measure,
+    ) -> OptimalLineBreakIterator<'a, T> {
+        OptimalLineBreakIterator {
+            max_width,
+            text,
+            measure,
+            tokens: self.peekable(),
+            pending: VecDeque::new(),
+            line_breaks: VecDeque::new(),
+            line_break: LineBreak::Wrap,
+            wrapped_white_space: 0,
+        }
+    }
+}
+
+/// Injects synthetic newlines into the token stream using the Knuth-Plass total-fit algorithm.
+///
+/// Each paragraph (the text up to a hard newline) is buffered and the breaks are chosen to
+/// minimize the sum of the demerits of all of its lines, instead of filling each line greedily.
+#[derive(Clone)]
+pub struct OptimalLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    /// Maximum display width for the lines
+    max_width: u32,
+
+    text: &'a str,
+    measure: &'a dyn Measure,
+
+    /// Tokens used to fill the paragraphs
+    tokens: Peekable<T>,
+
+    /// The tokens of the paragraph that has been broken into lines
+    pending: VecDeque<TokenKind>,
+
+    /// Why each synthetic newline in `pending` was added, and the width of the whitespace it
+    /// replaced
+    line_breaks: VecDeque<(LineBreak, u32)>,
+
+    /// Why the last line ended
+    line_break: LineBreak,
+
+    /// The width of the whitespace the last line wrapped at
+    wrapped_white_space: u32,
+}
+
+impl<'a, T> std::fmt::Debug for OptimalLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OptimalLineBreakIterator")
+            .field("max_width", &self.max_width)
+            .finish()
+    }
+}
+
+impl<'a, T> OptimalLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    /// Badness of a line with `slack` unused width, as in TeX with a stretchable right margin.
+    fn demerits(&self, slack: u32, is_last: bool, is_hyphen: bool) -> f64 {
+        let badness = if is_last || self.max_width == 0 {
+            0.0
+        } else {
+            100.0 * (f64::from(slack) / f64::from(self.max_width)).powi(3)
+        };
+
+        let penalty = if is_hyphen { HYPHEN_PENALTY } else { 0.0 };
+
+        (LINE_PENALTY + badness).powi(2) + penalty.powi(2)
+    }
+
+    /// Reads the next paragraph and breaks it into `pending`.
+    fn next_paragraph(&mut self) {
+        let mut items = Vec::new();
+        let mut newline = None;
+
+        // continues[i] is true if items[i] is the rest of a token that was split
+        let mut continues = Vec::new();
+
+        for token_kind in self.tokens.by_ref() {
+            match token_kind {
+                TokenKind::Required(token) => {
+                    let first = items.len();
+                    split(token, self.max_width, self.text, self.measure, &mut items);
+                    continues.resize(first + 1, false);
+                    continues.resize(items.len(), true);
+                }
+                TokenKind::Optional(_) | TokenKind::SoftHyphen(_) => {
+                    items.push(token_kind);
+                    continues.push(false);
+                }
+                TokenKind::Newline(_) => {
+                    newline.replace(token_kind);
+                    break;
+                }
+            }
+        }
+
+        // Widths of items[..i], soft hyphens only have a width at the end of a line
+        let mut prefix = Vec::with_capacity(items.len() + 1);
+        prefix.push(0u32);
+        for item in &items {
+            let width = if item.is_soft_hyphen() {
+                0
+            } else {
+                item.width()
+            };
+            prefix.push(prefix.last().unwrap() + width);
+        }
+
+        // Lines may break at `Optional` tokens (which are dropped), at soft hyphens or between two
+        // `Required` ones
+        let breaks: Vec<usize> = (0..=items.len())
+            .filter(|&i| {
+                i == 0
+                    || i == items.len()
+                    || items[i].is_optional()
+                    || items[i].is_soft_hyphen()
+                    || (items[i - 1].is_required() && items[i].is_required())
+            })
+            .collect();
+
+        // The soft hyphen a line ends with, if it breaks at one
+        let hyphen = |to: usize| items.get(to).filter(|item| item.is_soft_hyphen());
+
+        // The range of items on a line between two breaks, without leading or trailing whitespace
+        let is_visible = |i: &usize| !items[*i].is_optional() && !items[*i].is_soft_hyphen();
+        let line = |from: usize, to: usize| {
+            let start = (from..to).find(is_visible).unwrap_or(to);
+            let end = (start..to).rev().find(is_visible).map_or(start, |i| i + 1);
+            start..end
+        };
+
+        // best[i] is the total demerits and previous break of the best layout ending at breaks[i]
+        let mut best: Vec<(f64, usize)> = vec![(f64::INFINITY, 0); breaks.len()];
+        best[0] = (0.0, 0);
+
+        for to in 1..breaks.len() {
+            let is_last = to == breaks.len() - 1;
+
+            for from in (0..to).rev() {
+                let range = line(breaks[from], breaks[to]);
+                let hyphen_width = hyphen(breaks[to]).map_or(0, |item| item.width());
+                let width = prefix[range.end] - prefix[range.start] + hyphen_width;
+
+                if width > self.max_width && range.len() > 1 {
+                    // Starting earlier only makes the line wider
+                    break;
+                }
+
+                let slack = self.max_width.saturating_sub(width);
+                let is_hyphen = hyphen(breaks[to]).is_some();
+                let demerits = best[from].0 + self.demerits(slack, is_last, is_hyphen);
+                if demerits < best[to].0 {
+                    best[to] = (demerits, from);
+                }
+            }
+        }
+
+        // Walk back from the end of the paragraph to find the chosen breaks
+        let mut chosen = vec![breaks.len() - 1];
+        while let Some(&to) = chosen.last() {
+            if to == 0 {
+                break;
+            }
+            chosen.push(best[to].1);
+        }
+        chosen.reverse();
+
+        for (index, pair) in chosen.windows(2).enumerate() {
+            if index > 0 {
+                // The line before broke where this one starts
+                let start = breaks[pair[0]];
+                let line_break = if hyphen(start).is_some() {
+                    LineBreak::Hyphen
+                } else if continues[start] {
+                    LineBreak::Forced
+                } else {
+                    LineBreak::Wrap
+                };
+
+                // The whitespace between the lines is not drawn
+                let previous = line(breaks[chosen[index - 1]], start);
+                let next = line(start, breaks[pair[1]]);
+                let white_space = items[previous.end..next.start]
+                    .iter()
+                    .filter(|item| item.is_optional())
+                    .map(|item| item.width())
+                    .sum();
+
+                self.pending.push_back(TokenKind::Newline(None));
+                self.line_breaks.push_back((line_break, white_space));
+            }
+
+            // Soft hyphens within the line are not drawn
+            let range = line(breaks[pair[0]], breaks[pair[1]]);
+            self.pending.extend(
+                items[range]
+                    .iter()
+                    .filter(|item| !item.is_soft_hyphen())
+                    .copied(),
+            );
+            self.pending.extend(hyphen(breaks[pair[1]]).copied());
+        }
+
+        if let Some(newline) = newline {
+            // Whitespace at the end of the paragraph is kept, as `LineBreakIterator` does
+            let trailing = items
+                .iter()
+                .rev()
+                .take_while(|item| item.is_optional())
+                .count();
+            if trailing != items.len() {
+                self.pending
+                    .extend(items[items.len() - trailing..].iter().copied());
+            }
+
+            self.pending.push_back(newline);
+        }
+    }
+}
+
+impl<'a, T> Iterator for OptimalLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    type Item = TokenKind;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token_kind) = self.pending.pop_front() {
+                self.wrapped_white_space = 0;
+                match token_kind {
+                    TokenKind::Newline(None) => {
+                        let (line_break, white_space) = self.line_breaks.pop_front().unwrap();
+                        self.line_break = line_break;
+                        self.wrapped_white_space = white_space;
+                    }
+                    TokenKind::Newline(Some(_)) => self.line_break = LineBreak::Newline,
+                    _ => {}
+                }
+                return Some(token_kind);
+            }
+
+            // will return None if there is no more text
+            self.tokens.peek()?;
+            self.next_paragraph();
+        }
+    }
+}
+
+impl<'a, T> LineBreaks for OptimalLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn line_break(&self) -> LineBreak {
+        self.line_break
+    }
+
+    fn wrapped_white_space(&self) -> u32 {
+        self.wrapped_white_space
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{
+        grapheme_width::WithGraphemeWidth, line::Lines, whitespace::TokenizeWhiteSpace,
+        TTFParserMeasure,
+    };
+
+    use super::*;
+
+    /// Every char is one unit wide
+    #[derive(Debug)]
+    struct Monospace;
+
+    impl Measure for Monospace {
+        fn str(&self, text: &str) -> u32 {
+            text.chars().count() as u32
+        }
+
+        fn char(&self, _c: char) -> Option<u16> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn terminates() {
+        let measure = Monospace;
+
+        let text = "";
+        let mut tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(5, text, &measure);
+
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn total_fit() {
+        let measure = Monospace;
+
+        // Greedy filling gives "aaa bb", "cc", "ddddd"
+        let text = "aaa bb cc ddddd";
+        let lines: Vec<&str> = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(6, text, &measure)
+            .lines(text)
+            .collect();
+
+        assert_eq!(lines, vec!["aaa", "bb cc", "ddddd"]);
+    }
+
+    #[test]
+    fn paragraphs() {
+        let measure = Monospace;
+
+        let text = "  aaa bb cc ddddd\n\naaa bb cc ddddd ";
+        let lines: Vec<&str> = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(6, text, &measure)
+            .lines(text)
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec!["aaa", "bb cc", "ddddd", "", "aaa", "bb cc", "ddddd"]
+        );
+    }
+
+    #[test]
+    fn synthetic_newlines() {
+        let measure = Monospace;
+
+        let text = "aaa bb cc";
+        let mut tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(6, text, &measure);
+
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "aaa");
+        assert!(matches!(tokens.next(), Some(TokenKind::Optional(_))));
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "bb");
+
+        // The whitespace is replaced by a synthetic newline
+        assert!(matches!(tokens.next(), Some(TokenKind::Newline(None))));
+
+        let token = tokens.next().unwrap().into_token().unwrap();
+        assert_eq!(token.as_str(text), "cc");
+
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn long_words() {
+        let measure = Monospace;
+
+        let text = "a 1234567890 b";
+        let lines: Vec<&str> = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(4, text, &measure)
+            .lines(text)
+            .collect();
+
+        assert_eq!(lines, vec!["a", "1234", "5678", "90 b"]);
+    }
+
+    #[test]
+    fn no_width() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "word";
+        let lines: Vec<&str> = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(0, text, &measure)
+            .lines(text)
+            .collect();
+
+        assert_eq!(lines, vec!["w", "o", "r", "d"]);
+    }
+
+    #[test]
+    fn soft_hyphens() {
+        let measure = Monospace;
+
+        let text = "aaaa bbbbbb\u{AD}ccccccc";
+        let mut tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(12, text, &measure);
+
+        let lines: Vec<&str> = tokens.clone().lines(text).collect();
+        assert_eq!(lines, vec!["aaaa bbbbbb\u{AD}", "ccccccc"]);
+
+        assert!(tokens.any(|token_kind| token_kind.is_soft_hyphen()));
+
+        // The soft hyphen is not used when the word fits
+        let mut tokens = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .add_optimal_newlines_at(20, text, &measure);
+
+        assert!(!tokens.any(|token_kind| token_kind.is_soft_hyphen()));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    alignment::Alignment,
+    grapheme_width::{GraphemeWidthIterator, WithGraphemeWidth},
+    line::{LineIterator, Lines},
+    line_widths::LineWidths,
+    metrics::{Metrics, MetricsIterator},
+    optimal_line_break::{AddOptimalNewlines, OptimalLineBreakIterator},
+    position::{PositionIterator, Positions},
+    whitespace::{TokenizeWhiteSpace, WhiteSpaceIterator},
+    wordwrap::{WordWrap, WordWrapWithMetrics, WordWrapWithPosition},
+    Measure, VerticalMetrics,
+};
+
+/// WordWrap for variable-width TTF text that breaks whole paragraphs at once.
+///
+/// `WhiteSpaceWordWrap` fills every line as much as possible, which can leave the following lines
+/// ragged. `OptimalWordWrap` uses the Knuth-Plass total-fit algorithm to choose the line breaks
+/// that leave the least unused space across the paragraph.
+///
+/// Every line is `max_width` wide, there are no per-line widths or indents.
+#[derive(Debug)]
+pub struct OptimalWordWrap<'fnt> {
+    max_width: u32,
+    measure: &'fnt dyn Measure,
+    alignment: Alignment,
+    vertical_metrics: Option<VerticalMetrics>,
+}
+
+impl<'fnt> OptimalWordWrap<'fnt> {
+    /// Creates a new `OptimalWordWrap`
+    ///
+    /// Will wrap at `max_width` and measure the glyphs using `font_face`
+    pub fn new(max_width: u32, measure: &'fnt dyn Measure) -> Self {
+        Self {
+            max_width,
+            measure,
+            alignment: Alignment::Left,
+            vertical_metrics: None,
+        }
+    }
+
+    /// Places the lines of `wrap_with_position()` within `max_width` using `alignment`.
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Places the lines of `wrap_with_metrics()` vertically using `vertical_metrics`.
+    pub fn with_vertical_metrics(mut self, vertical_metrics: VerticalMetrics) -> Self {
+        self.vertical_metrics = Some(vertical_metrics);
+        self
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrap<'m, 'txt> for OptimalWordWrap<'m> {
+    type Iterator = LineIterator<
+        'txt,
+        OptimalLineBreakIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>,
+    >;
+
+    fn word_wrap(&self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .add_optimal_newlines_at(self.max_width, text, self.measure)
+            .lines(text)
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithPosition<'m, 'txt> for OptimalWordWrap<'m> {
+    type Iterator = PositionIterator<
+        'm,
+        OptimalLineBreakIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>,
+    >;
+
+    fn word_wrap_with_position(&'m self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .add_optimal_newlines_at(self.max_width, text, self.measure)
+            .positions(text, self.measure)
+            .aligned(self.alignment, LineWidths::new(self.max_width))
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithMetrics<'m, 'txt> for OptimalWordWrap<'m> {
+    type Iterator = MetricsIterator<
+        OptimalLineBreakIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>,
+    >;
+
+    fn word_wrap_with_metrics(&'m self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .add_optimal_newlines_at(self.max_width, text, self.measure)
+            .metrics()
+            .with_vertical_metrics(self.vertical_metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::{Face, GlyphId};
+
+    use crate::{
+        CharPosition, Position, TTFParserMeasure, WhiteSpaceWordWrap, Wrap, WrapWithPosition,
+    };
+
+    use super::*;
+
+    #[test]
+    fn nomicon() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "The nethermost caverns are not for the fathoming of eyes that see; for their marvels are strange and terrific. Cursed the ground where dead thoughts live new and oddly bodied, and evil the mind that is held by no head.";
+
+        let greedy: Vec<&str> = text
+            .wrap(&WhiteSpaceWordWrap::new(20000, &measure))
+            .collect();
+        let optimal: Vec<&str> = text.wrap(&OptimalWordWrap::new(20000, &measure)).collect();
+
+        assert_eq!(
+            optimal,
+            vec![
+                "The nethermost",
+                "caverns are not for",
+                "the fathoming of",
+                "eyes that see; for",
+                "their marvels are",
+                "strange and terrific.",
+                "Cursed the ground",
+                "where dead thoughts",
+                "live new and oddly",
+                "bodied, and evil the",
+                "mind that is held by",
+                "no head.",
+            ]
+        );
+
+        // The same words are used, only the breaks move
+        assert_eq!(greedy.join(" "), optimal.join(" "));
+        assert!(optimal.iter().all(|line| measure.str(line) <= 20000));
+    }
+
+    #[test]
+    fn positions() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = OptimalWordWrap::new(0, &measure);
+
+        let positions: Vec<CharPosition> = "word".wrap_with_position(&word_wrap).collect();
+        assert_eq!(
+            positions,
+            vec![
+                CharPosition::Known(Position {
+                    ch: 'w',
+                    index: 0,
+                    char_index: 0,
+                    line: 0,
+                    offset: 0,
+                    width: 1539,
+                    span: None,
+                    face: None,
+                    glyph_id: Some(GlyphId(91)),
+                }),
+                CharPosition::Known(Position {
+                    ch: 'o',
+                    index: 1,
+                    char_index: 1,
+                    line: 1,
+                    offset: 0,
+                    width: 1168,
+                    span: None,
+                    face: None,
+                    glyph_id: Some(GlyphId(83)),
+                }),
+                CharPosition::Known(Position {
+                    ch: 'r',
+                    index: 2,
+                    char_index: 2,
+                    line: 2,
+                    offset: 0,
+                    width: 693,
+                    span: None,
+                    face: None,
+                    glyph_id: Some(GlyphId(86)),
+                }),
+                CharPosition::Known(Position {
+                    ch: 'd',
+                    index: 3,
+                    char_index: 3,
+                    line: 3,
+                    offset: 0,
+                    width: 1155,
+                    span: None,
+                    face: None,
+                    glyph_id: Some(GlyphId(72)),
+                }),
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::iter::Peekable;
+use std::collections::VecDeque;
 
 use crate::{
     token::{Token, TokenKind},
@@ -31,8 +31,10 @@ where
             text,
             measure,
             max_width,
-            tokens: self.peekable(),
+            tokens: self,
+            ahead: VecDeque::new(),
             partial: None,
+            split: false,
         }
     }
 }
@@ -68,6 +70,12 @@ pub trait PartialTokens {
 
     fn next(&mut self, space_remaining: u32) -> Option<Self::Item>;
     fn peek(&mut self, space_remaining: u32) -> Option<Self::Item>;
+
+    /// Whether the next token is the rest of a token that was split
+    fn is_split(&self) -> bool;
+
+    /// Sets the width of the line being filled, tokens wider than it are split.
+    fn set_max_width(&mut self, max_width: u32);
 }
 
 /// Splits tokens that are wider than the max_width
@@ -79,8 +87,14 @@ where
     text: &'a str,
     measure: &'a dyn Measure,
     max_width: u32,
-    tokens: Peekable<T>,
+    tokens: T,
+
+    /// Tokens that were peeked at, in order
+    ahead: VecDeque<TokenKind>,
     partial: Option<TokenKind>,
+
+    /// `partial` is the tail of a split token
+    split: bool,
 }
 
 // Takes a token and space remaining, returns a tuple of the head and tail of the split token.
@@ -88,19 +102,37 @@ impl<'a, T> PartialTokensIterator<'a, T>
 where
     T: Iterator<Item = TokenKind>,
 {
+    /// The `n`th token after the current one, without consuming it
+    fn lookahead(&mut self, n: usize) -> Option<TokenKind> {
+        while self.ahead.len() <= n {
+            let token_kind = self.tokens.next()?;
+            self.ahead.push_back(token_kind);
+        }
+        self.ahead.get(n).copied()
+    }
+
+    /// The width of the hyphen drawn after a token if `following` is a soft hyphen
+    fn hyphen_width(following: Option<TokenKind>) -> u32 {
+        match following {
+            Some(TokenKind::SoftHyphen(token)) => token.display_width,
+            _ => 0,
+        }
+    }
+
     fn process_partial(
         &mut self,
         is_peek: bool,
         token_kind: TokenKind,
         space_remaining: u32,
+        hyphen_width: u32,
     ) -> Option<PartialToken> {
         let kind = token_kind.kind();
 
         let token = match token_kind {
             TokenKind::Required(token) | TokenKind::Optional(token) => token,
-            newline @ TokenKind::Newline(_) => {
-                // Newlines pass though
-                return Some(PartialToken::Token(newline));
+            token_kind @ TokenKind::Newline(_) | token_kind @ TokenKind::SoftHyphen(_) => {
+                // Newlines and soft hyphens pass though
+                return Some(PartialToken::Token(token_kind));
             }
         };
 
@@ -132,13 +164,22 @@ where
             if let Some(tail) = tail {
                 if !is_peek {
                     self.partial.replace(kind.token(tail));
+
+                    // The tail is the whole token if nothing fit on the line
+                    if !matches!(head, Some(PartialToken::EndOfLine)) {
+                        self.split = true;
+                    }
                 }
             }
 
             head
-        } else if token.display_width > space_remaining {
+        } else if token.display_width > space_remaining
+            || (token.display_width + hyphen_width > space_remaining
+                && space_remaining < self.max_width)
+        {
             // If the word is not wider than the max width and the line doesn't have room, return
-            // None
+            // None. A syllable must fit with the hyphen that would be drawn after it, unless it
+            // starts the line.
             if !is_peek {
                 self.partial.replace(kind.token(token));
             }
@@ -157,23 +198,34 @@ where
     type Item = PartialToken;
 
     fn next(&mut self, space_remaining: u32) -> Option<PartialToken> {
-        match self.partial.take() {
-            Some(partial) => self.process_partial(false, partial, space_remaining),
+        let token = match self.partial.take() {
+            Some(partial) => partial,
             None => {
-                let token = self.tokens.next()?;
-                self.process_partial(false, token, space_remaining)
+                let token = self.lookahead(0)?;
+                self.ahead.pop_front();
+                self.split = false;
+                token
             }
-        }
+        };
+        let hyphen_width = Self::hyphen_width(self.lookahead(0));
+        self.process_partial(false, token, space_remaining, hyphen_width)
     }
 
     fn peek(&mut self, space_remaining: u32) -> Option<PartialToken> {
-        match self.partial.clone() {
-            Some(partial) => self.process_partial(true, partial, space_remaining),
-            None => {
-                let token = self.tokens.peek()?.clone();
-                self.process_partial(true, token, space_remaining)
-            }
-        }
+        let (token, following) = match self.partial {
+            Some(partial) => (partial, self.lookahead(0)),
+            None => (self.lookahead(0)?, self.lookahead(1)),
+        };
+        let hyphen_width = Self::hyphen_width(following);
+        self.process_partial(true, token, space_remaining, hyphen_width)
+    }
+
+    fn is_split(&self) -> bool {
+        self.partial.is_some() && self.split
+    }
+
+    fn set_max_width(&mut self, max_width: u32) {
+        self.max_width = max_width;
     }
 }
 
@@ -316,7 +368,7 @@ mod tests {
             .with_partial_tokens(0, text, &measure);
 
         let token = partials.next(0).unwrap().into_token().unwrap();
-        assert_eq!("T", token.as_str(&text));
+        assert_eq!("T", token.as_str(text));
 
         let token = partials.next(0);
         assert!(token.is_none());
@@ -1,6 +1,16 @@
-use std::{fmt::Formatter, str::Chars};
+use std::{collections::VecDeque, fmt::Formatter, rc::Rc, str::Chars};
 
-use crate::{token::TokenKind, Measure};
+use ttf_parser::GlyphId;
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level};
+
+use crate::{
+    alignment::Alignment,
+    grapheme_position::GraphemePositionIterator,
+    line_widths::LineWidths,
+    metrics::{LineBreak, LineBreaks},
+    token::{TokenKind, HYPHEN},
+    Measure, WhiteSpace,
+};
 
 /// The position of a char, if known.
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -9,7 +19,38 @@ pub enum CharPosition {
     Known(Position),
 
     /// The position of `char` is not known because `Measure` did not known it's size.
-    Unknown(char),
+    Unknown(UnknownChar),
+}
+
+impl CharPosition {
+    /// The byte index of the char in the text
+    pub fn index(&self) -> usize {
+        match self {
+            CharPosition::Known(position) => position.index,
+            CharPosition::Unknown(unknown) => unknown.index,
+        }
+    }
+
+    /// The index of the char in the chars of the text
+    pub fn char_index(&self) -> usize {
+        match self {
+            CharPosition::Known(position) => position.char_index,
+            CharPosition::Unknown(unknown) => unknown.char_index,
+        }
+    }
+}
+
+/// A `char` that the `Measure` does not know the size of
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct UnknownChar {
+    /// The `char` that is not in the font
+    pub ch: char,
+
+    /// The byte index of the char in the text
+    pub index: usize,
+
+    /// The index of the char in the chars of the text
+    pub char_index: usize,
 }
 
 /// A `char`s position in lines of text
@@ -18,6 +59,12 @@ pub struct Position {
     /// The `char` for the font glyph
     pub ch: char,
 
+    /// The byte index of the char in the text, a hyphen has the index of its soft hyphen
+    pub index: usize,
+
+    /// The index of the char in the chars of the text
+    pub char_index: usize,
+
     /// The line that this char is on
     pub line: u32,
 
@@ -26,6 +73,25 @@ pub struct Position {
 
     /// The width of the char
     pub width: u16,
+
+    /// The span the char is in, if measured with a `SpanMeasure`
+    pub span: Option<usize>,
+
+    /// The face that measured the char, if measured with a `FallbackMeasure`
+    pub face: Option<usize>,
+
+    /// The glyph of the char in that face, if the `Measure` provides it
+    pub glyph_id: Option<GlyphId>,
+}
+
+/// Whether `text` has chars that are laid out right-to-left, without analysing it all.
+pub(crate) fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            bidi_class(c),
+            BidiClass::R | BidiClass::AL | BidiClass::RLE | BidiClass::RLO | BidiClass::RLI
+        )
+    })
 }
 
 pub trait Positions<T> {
@@ -37,13 +103,29 @@ where
     T: Iterator<Item = TokenKind>,
 {
     fn positions<'a>(self, text: &'a str, measure: &'a dyn Measure) -> PositionIterator<'a, T> {
+        // Only text with right-to-left chars needs to be reordered
+        let bidi = has_rtl(text).then(|| Rc::new(BidiInfo::new(text, None)));
+
         PositionIterator {
             chars: None,
+            index: 0,
+            char_cursor: (0, 0),
+            previous: None,
             display_offset: 0,
             line: 0,
             measure,
             text,
             tokens: self,
+            alignment: Alignment::Left,
+            line_widths: LineWidths::new(0),
+            is_first_line: true,
+            line_tokens: VecDeque::new(),
+            extra_space: VecDeque::new(),
+            pending_space: 0,
+            bidi,
+            visual: VecDeque::new(),
+            pending: None,
+            white_space: None,
         }
     }
 }
@@ -52,11 +134,45 @@ where
 #[derive(Clone)]
 pub struct PositionIterator<'a, T> {
     chars: Option<Chars<'a>>,
+    /// The byte index of the next char in `text`
+    index: usize,
+    /// A byte index in `text` and its char index, to count chars on from
+    char_cursor: (usize, usize),
+    /// The previous char of the token, for kerning
+    previous: Option<char>,
     display_offset: u32,
     line: u32,
     measure: &'a dyn Measure,
     text: &'a str,
     tokens: T,
+
+    /// How the lines are placed within `line_widths`
+    alignment: Alignment,
+    line_widths: LineWidths,
+
+    /// The current line starts a paragraph
+    is_first_line: bool,
+
+    /// The tokens of the current line
+    line_tokens: VecDeque<TokenKind>,
+
+    /// The width added after each `Optional` token of the line, to justify it
+    extra_space: VecDeque<u32>,
+
+    /// The width to add before the next token
+    pending_space: u32,
+
+    /// The embedding levels of text with right-to-left chars
+    bidi: Option<Rc<BidiInfo<'a>>>,
+
+    /// The positions of the current line, in visual order
+    visual: VecDeque<CharPosition>,
+
+    /// The first position of the next line, with its line
+    pending: Option<(u32, CharPosition)>,
+
+    /// Whether runs of whitespace are drawn as a single space
+    white_space: Option<WhiteSpace>,
 }
 
 impl<'a, T> std::fmt::Debug for PositionIterator<'a, T>
@@ -68,34 +184,152 @@ where
     }
 }
 
-impl<'a, T> Iterator for PositionIterator<'a, T>
+impl<'a, T> PositionIterator<'a, T>
 where
-    T: Iterator<Item = TokenKind>,
+    T: Iterator<Item = TokenKind> + LineBreaks,
 {
-    type Item = CharPosition;
+    /// The tokens the positions are made of.
+    pub fn tokens(&self) -> &T {
+        &self.tokens
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// The embedding levels of the text, if it has right-to-left chars.
+    pub(crate) fn bidi(&self) -> Option<Rc<BidiInfo<'a>>> {
+        self.bidi.clone()
+    }
+
+    /// Provides a `GraphemePosition` for each grapheme cluster instead of a `CharPosition` for
+    /// each char.
+    pub fn graphemes(self) -> GraphemePositionIterator<'a, Self> {
+        GraphemePositionIterator::new(self.text, self)
+    }
+
+    /// Places each line within its width in `line_widths` using `alignment`.
+    pub fn aligned(mut self, alignment: Alignment, line_widths: LineWidths) -> Self {
+        self.alignment = alignment;
+        self.line_widths = line_widths;
+        self
+    }
+
+    /// Draws the whitespace of the text as `white_space` does.
+    pub fn with_white_space(mut self, white_space: Option<WhiteSpace>) -> Self {
+        self.white_space = white_space;
+        self
+    }
+
+    /// Reads the tokens of the next line and sets the offset it starts at.
+    fn next_line(&mut self) {
+        let mut is_wrapped = false;
+        let mut is_paragraph_end = false;
+        for token_kind in self.tokens.by_ref() {
+            self.line_tokens.push_back(token_kind);
+
+            if let TokenKind::Newline(token) = token_kind {
+                // A word broken because it is wider than the line is not stretched
+                is_wrapped = token.is_none() && self.tokens.line_break() != LineBreak::Forced;
+                is_paragraph_end = token.is_some();
+                break;
+            }
+        }
+
+        if self.line_tokens.is_empty() {
+            // The text has ended, the offset is where it ended
+            return;
+        }
+
+        let max_width = self.line_widths.width(self.line, self.is_first_line);
+        let (offset, extra_space) =
+            self.alignment
+                .align(self.line_tokens.iter(), max_width, is_wrapped);
+        self.display_offset = self.line_widths.indent(self.is_first_line) + offset;
+        self.extra_space = extra_space.into();
+        self.pending_space = 0;
+        self.is_first_line = is_paragraph_end;
+    }
+
+    /// The next token of the line, with the space before it added to the offset.
+    fn next_token(&mut self) -> Option<TokenKind> {
+        if self.line_tokens.is_empty() {
+            self.next_line();
+        }
+
+        let token_kind = self.line_tokens.pop_front()?;
+        if !token_kind.is_newline() {
+            self.display_offset += std::mem::take(&mut self.pending_space);
+        }
+        if token_kind.is_optional() {
+            self.pending_space = self.extra_space.pop_front().unwrap_or_default();
+        }
+
+        Some(token_kind)
+    }
+
+    /// The offset on the current line that the next position starts at.
+    pub(crate) fn offset(&self) -> u32 {
+        self.display_offset
+    }
+
+    /// The index in the chars of the text of the char at byte `index`.
+    pub(crate) fn char_index(&mut self, index: usize) -> usize {
+        let (byte, char_index) = match self.char_cursor {
+            (byte, _) if index < byte => (0, 0),
+            cursor => cursor,
+        };
+
+        let char_index = char_index + self.text[byte..index].chars().count();
+        self.char_cursor = (index, char_index);
+        char_index
+    }
+
+    /// Measures `ch`, at byte `index` of the text, and creates its `CharPosition` at the current
+    /// offset.
+    fn position(&mut self, index: usize, ch: char) -> CharPosition {
+        let offset = self.display_offset;
+        let char_index = self.char_index(index);
+        // add this glyph's width to the display_offset
+        let (char_width, glyph_id) = self.measure.char_glyph_at(index, ch);
+        match char_width {
+            Some(char_width) => {
+                self.display_offset += u32::from(char_width);
+                CharPosition::Known(Position {
+                    ch,
+                    index,
+                    char_index,
+                    line: self.line,
+                    offset,
+                    width: char_width,
+                    span: self.measure.span_at(index),
+                    face: self.measure.face_at(index, ch),
+                    glyph_id,
+                })
+            }
+            None => CharPosition::Unknown(UnknownChar {
+                ch,
+                index,
+                char_index,
+            }),
+        }
+    }
+
+    /// The next position in logical order.
+    fn next_logical(&mut self) -> Option<CharPosition> {
         loop {
             match self.chars.as_mut() {
                 Some(chars) => match chars.next() {
                     Some(ch) => {
-                        // There is a char! Measure it and create the Position
-                        let offset = self.display_offset;
-                        // add this glyph's width to the display_offset
-                        let next_item = match self.measure.char(ch) {
-                            Some(char_width) => {
-                                self.display_offset += u32::from(char_width);
-                                CharPosition::Known(Position {
-                                    ch,
-                                    line: self.line,
-                                    offset,
-                                    width: char_width,
-                                })
-                            }
-                            None => CharPosition::Unknown(ch),
+                        let index = self.index;
+                        self.index += ch.len_utf8();
+
+                        // Kerning only applies between the chars of a token
+                        if let Some(left) = self.previous.replace(ch) {
+                            let kerning = self.measure.kerning_at(index, left, ch);
+                            self.display_offset = self
+                                .display_offset
+                                .saturating_add_signed(i32::from(kerning));
                         }
-                        .into();
-                        return next_item;
+
+                        // There is a char! Measure it and create the Position
+                        return Some(self.position(index, ch));
                     }
                     None => {
                         // there are no more chars, set it to None and retry
@@ -103,17 +337,29 @@ where
                         continue;
                     }
                 },
-                None => match self.tokens.next() {
+                None => match self.next_token() {
                     Some(TokenKind::Newline(_)) => {
                         // Increment the line and call again
                         self.line += 1;
                         self.display_offset = 0;
                         continue;
                     }
+                    Some(TokenKind::SoftHyphen(token)) => {
+                        // The line breaks at the soft hyphen, draw a hyphen
+                        return Some(self.position(token.start, HYPHEN));
+                    }
+                    Some(TokenKind::Optional(token))
+                        if self.white_space.is_some_and(WhiteSpace::collapses) =>
+                    {
+                        // The whitespace collapsed into a space
+                        return Some(self.position(token.start, ' '));
+                    }
                     Some(TokenKind::Optional(token)) | Some(TokenKind::Required(token)) => {
                         // There is another token, prep chars
                         let chars = self.text[token.start..token.end].chars();
                         self.chars.replace(chars);
+                        self.index = token.start;
+                        self.previous.take();
                         continue;
                     }
                     None => {
@@ -124,6 +370,108 @@ where
             }
         }
     }
+
+    /// Reads the positions of the next line and reorders them visually.
+    fn next_visual_line(&mut self) -> Option<()> {
+        let (line, position) = match self.pending.take() {
+            Some(pending) => pending,
+            None => {
+                let position = self.next_logical()?;
+                (self.line, position)
+            }
+        };
+
+        let mut positions = vec![position];
+        while let Some(position) = self.next_logical() {
+            if self.line != line {
+                self.pending = Some((self.line, position));
+                break;
+            }
+            positions.push(position);
+        }
+
+        self.reorder(&positions);
+        Some(())
+    }
+
+    /// Queues up the `positions` of a line in visual order, with their offsets from left to right.
+    fn reorder(&mut self, positions: &[CharPosition]) {
+        let bidi = match self.bidi.as_ref() {
+            Some(bidi) => bidi,
+            None => return,
+        };
+
+        let mut levels: Vec<Level> = positions
+            .iter()
+            .map(|position| bidi.levels[position.index()])
+            .collect();
+
+        // Whitespace at the end of a line takes the level of the paragraph
+        let paragraph = bidi
+            .paragraphs
+            .iter()
+            .find(|paragraph| paragraph.range.contains(&positions[0].index()));
+        if let Some(paragraph) = paragraph {
+            let trailing = positions
+                .iter()
+                .rev()
+                .take_while(|position| {
+                    self.text[position.index()..]
+                        .chars()
+                        .next()
+                        .is_some_and(char::is_whitespace)
+                })
+                .count();
+            let line_end = levels.len();
+            levels[line_end - trailing..].fill(paragraph.level);
+        }
+
+        // The space each position takes up, including kerning and justification
+        let known: Vec<(usize, Position)> = positions
+            .iter()
+            .enumerate()
+            .filter_map(|(order, position)| match position {
+                CharPosition::Known(position) => Some((order, *position)),
+                CharPosition::Unknown(_) => None,
+            })
+            .collect();
+        let mut advances = vec![0; positions.len()];
+        for (pair, (order, position)) in known.iter().enumerate() {
+            advances[*order] = match known.get(pair + 1) {
+                Some((_, next)) => next.offset.saturating_sub(position.offset),
+                None => u32::from(position.width),
+            };
+        }
+
+        let mut offset = known.first().map_or(0, |(_, position)| position.offset);
+        for order in BidiInfo::reorder_visual(&levels) {
+            let mut position = positions[order];
+            if let CharPosition::Known(known) = &mut position {
+                known.offset = offset;
+                offset += advances[order];
+            }
+            self.visual.push_back(position);
+        }
+    }
+}
+
+impl<'a, T> Iterator for PositionIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind> + LineBreaks,
+{
+    type Item = CharPosition;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bidi.is_none() {
+            return self.next_logical();
+        }
+
+        // Right-to-left text is reordered a line at a time
+        if self.visual.is_empty() {
+            self.next_visual_line()?;
+        }
+        self.visual.pop_front()
+    }
 }
 
 #[cfg(test)]
@@ -133,6 +481,7 @@ mod tests {
     use crate::{
         grapheme_width::WithGraphemeWidth, line_break::AddNewlines,
         partial_tokens::WithPartialTokens, whitespace::TokenizeWhiteSpace, TTFParserMeasure,
+        WhiteSpaceWordWrap, Wrap, WrapWithPosition,
     };
 
     use super::*;
@@ -173,9 +522,14 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'a',
+                index: 0,
+                char_index: 0,
                 line: 0,
                 offset: 0,
-                width: 1114
+                width: 1114,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(69)),
             })
         ));
 
@@ -201,9 +555,14 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'A',
+                index: 0,
+                char_index: 0,
                 line: 0,
                 offset: 0,
-                width: 1336
+                width: 1336,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(37)),
             },)
         ));
 
@@ -212,9 +571,14 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'B',
+                index: 1,
+                char_index: 1,
                 line: 0,
                 offset: 1336,
-                width: 1275
+                width: 1275,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(38)),
             },)
         ));
 
@@ -223,9 +587,14 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'C',
+                index: 3,
+                char_index: 3,
                 line: 1,
                 offset: 0,
-                width: 1333
+                width: 1333,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(39)),
             },)
         ));
 
@@ -234,15 +603,80 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'D',
+                index: 4,
+                char_index: 4,
                 line: 1,
                 offset: 1333,
-                width: 1343
+                width: 1343,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(40)),
             },)
         ));
 
         assert!(positions.next().is_none());
     }
 
+    #[test]
+    fn kerning() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "To T";
+        let mut positions = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(20_000, text, &measure)
+            .add_newlines_at(20_000)
+            .positions(text, &measure);
+
+        let token = positions.next().unwrap();
+        assert!(matches!(
+            token,
+            CharPosition::Known(Position {
+                ch: 'T',
+                index: 0,
+                char_index: 0,
+                line: 0,
+                offset: 0,
+                width: 1222,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(56)),
+            })
+        ));
+
+        // 'o' is kerned towards the 'T'
+        let token = positions.next().unwrap();
+        assert!(matches!(
+            token,
+            CharPosition::Known(Position {
+                ch: 'o',
+                index: 1,
+                char_index: 1,
+                line: 0,
+                offset: 1123,
+                width: 1168,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(83)),
+            })
+        ));
+
+        // There is no kerning between tokens
+        let token = positions.next().unwrap();
+        assert!(matches!(
+            token,
+            CharPosition::Known(Position {
+                ch: ' ',
+                line: 0,
+                offset: 2291,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_y() {
         let font_data = crate::tests::read_font();
@@ -263,14 +697,58 @@ mod tests {
             token,
             CharPosition::Known(Position {
                 ch: 'y',
+                index: 0,
+                char_index: 0,
                 line: 0,
                 offset: 0,
-                width: 969
+                width: 969,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(93)),
             },)
         ));
 
+        // The combining mark is not in the font, it still has its byte and char index
         let token = positions.next().unwrap();
-        assert!(matches!(token, CharPosition::Unknown('\u{306}')));
+        assert_eq!(
+            token,
+            CharPosition::Unknown(UnknownChar {
+                ch: '\u{306}',
+                index: 1,
+                char_index: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn indices() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(0, &measure);
+
+        // Chars after the two bytes of the "ï" have a byte index one more than their char index
+        let indices: Vec<(char, usize, usize)> = "naïve\ncafé"
+            .wrap_with_position(&word_wrap)
+            .map(|position| match position {
+                CharPosition::Known(position) => (position.ch, position.index, position.char_index),
+                CharPosition::Unknown(unknown) => (unknown.ch, unknown.index, unknown.char_index),
+            })
+            .collect();
+        assert_eq!(
+            indices,
+            vec![
+                ('n', 0, 0),
+                ('a', 1, 1),
+                ('ï', 2, 2),
+                ('v', 4, 3),
+                ('e', 5, 4),
+                ('c', 7, 6),
+                ('a', 8, 7),
+                ('f', 9, 8),
+                ('é', 10, 9),
+            ]
+        );
     }
 
     #[test]
@@ -293,12 +771,167 @@ mod tests {
             token,
             Some(CharPosition::Known(Position {
                 ch: 'T',
+                index: 0,
+                char_index: 0,
                 line: 0,
                 offset: 0,
-                width: 1222
+                width: 1222,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(56)),
             }))
         ));
 
         assert!(positions.next().is_none());
     }
+
+    #[test]
+    fn indents() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = WhiteSpaceWordWrap::new(10000, &measure)
+            .with_first_line_indent(2000)
+            .with_hanging_indent(1000);
+
+        let text = "12345 678\n9";
+        let lines: Vec<&str> = text.wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["12345", "678", "9"]);
+
+        // The first line of each paragraph and the lines after it are indented
+        let positions: Vec<Position> = text
+            .wrap_with_position(&word_wrap)
+            .filter_map(|position| match position {
+                CharPosition::Known(position) => Some(position),
+                CharPosition::Unknown(_) => None,
+            })
+            .collect();
+        let mut offsets = vec![(positions[0].line, positions[0].offset)];
+        offsets.extend(
+            positions
+                .windows(2)
+                .filter(|pair| pair[0].line != pair[1].line)
+                .map(|pair| (pair[1].line, pair[1].offset)),
+        );
+        assert_eq!(offsets, vec![(0, 2000), (1, 1000), (2, 2000)]);
+    }
+
+    #[test]
+    fn rtl_chars() {
+        // The same texts as the full analysis finds right-to-left chars in
+        for text in [
+            "abc",
+            "abc \u{5D0}\u{5D1}",
+            "\u{627}1",
+            "1 \u{663}",
+            "a\u{202E}b",
+            "",
+        ] {
+            assert_eq!(
+                has_rtl(text),
+                BidiInfo::new(text, None).has_rtl(),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn bidi() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &measure);
+
+        let known = |text: &str| -> Vec<(char, usize, u32)> {
+            text.wrap_with_position(&word_wrap)
+                .filter_map(|position| match position {
+                    CharPosition::Known(position) => {
+                        Some((position.ch, position.index, position.offset))
+                    }
+                    CharPosition::Unknown(_) => None,
+                })
+                .collect()
+        };
+
+        // The right-to-left override is drawn in reverse, from left to right
+        let a = measure.str("x ");
+        let b = a + measure.str("c");
+        let c = b + measure.str("b");
+        assert_eq!(
+            &known("x \u{202E}abc\u{202C} y")[..5],
+            &[
+                ('x', 0, 0),
+                (' ', 1, measure.str("x")),
+                ('c', 7, a),
+                ('b', 6, b),
+                ('a', 5, c)
+            ]
+        );
+
+        // A right-to-left paragraph starts on the right
+        let positions: Vec<CharPosition> =
+            "\u{5E9}\u{5DC} ok".wrap_with_position(&word_wrap).collect();
+        assert!(matches!(
+            positions[0],
+            CharPosition::Known(Position {
+                ch: 'o',
+                index: 5,
+                char_index: 3,
+                offset: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            positions[4],
+            CharPosition::Unknown(UnknownChar {
+                ch: '\u{5E9}',
+                index: 0,
+                char_index: 0,
+            })
+        ));
+    }
+
+    #[test]
+    fn soft_hyphen() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "co\u{AD}op\u{AD}er";
+        let positions: Vec<CharPosition> = text
+            .with_grapheme_width(&measure)
+            .tokenize_white_space(&measure)
+            .with_partial_tokens(6000, text, &measure)
+            .add_newlines_at(6000)
+            .positions(text, &measure)
+            .collect();
+
+        // Only the soft hyphen the line breaks at is drawn, as a hyphen
+        assert_eq!(positions.len(), 7);
+        assert!(matches!(
+            positions[4],
+            CharPosition::Known(Position {
+                ch: '-',
+                index: 6,
+                char_index: 5,
+                line: 0,
+                offset: 4557,
+                width: 565,
+                span: None,
+                face: None,
+                glyph_id: Some(GlyphId(17)),
+            })
+        ));
+        assert!(matches!(
+            positions[5],
+            CharPosition::Known(Position {
+                ch: 'e',
+                line: 1,
+                offset: 0,
+                ..
+            })
+        ));
+    }
 }
@@ -0,0 +1,157 @@
+use rustybuzz::UnicodeBuffer;
+use ttf_parser::{Face, GlyphId};
+
+use crate::{
+    shaping::{Glyph, Shape},
+    Measure,
+};
+
+/// Implements measuring and shaping glyphs via `rustybuzz`
+///
+/// Requires the `shaping` feature.
+#[derive(Clone)]
+pub struct RustybuzzMeasure<'a> {
+    face: rustybuzz::Face<'a>,
+}
+
+impl<'a> RustybuzzMeasure<'a> {
+    /// Creates a new RustybuzzMeasure for the font `face`.
+    pub fn new(face: &Face<'a>) -> Self {
+        Self {
+            face: rustybuzz::Face::from_face(face.clone()),
+        }
+    }
+}
+
+impl<'a> std::fmt::Debug for RustybuzzMeasure<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RustybuzzMeasure").finish()
+    }
+}
+
+impl<'a> Shape for RustybuzzMeasure<'a> {
+    fn shape(&self, text: &str) -> Vec<Glyph> {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+
+        let glyph_buffer = rustybuzz::shape(&self.face, &[], buffer);
+
+        glyph_buffer
+            .glyph_infos()
+            .iter()
+            .zip(glyph_buffer.glyph_positions())
+            .map(|(info, position)| Glyph {
+                glyph_id: GlyphId(info.glyph_id as u16),
+                cluster: info.cluster as usize,
+                x_advance: position.x_advance,
+                x_offset: position.x_offset,
+                y_offset: position.y_offset,
+            })
+            .collect()
+    }
+}
+
+impl<'a> Measure for RustybuzzMeasure<'a> {
+    fn str(&self, text: &str) -> u32 {
+        let width: i32 = self.shape(text).iter().map(|glyph| glyph.x_advance).sum();
+        width.max(0) as u32
+    }
+
+    fn char(&self, c: char) -> Option<u16> {
+        self.face.glyph_index(c)?;
+
+        let width = self.str(c.encode_utf8(&mut [0; 4]));
+        Some(width.min(u32::from(u16::MAX)) as u16)
+    }
+
+    fn glyph(&self, c: char) -> Option<GlyphId> {
+        self.face.glyph_index(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GlyphPosition, ShapingWordWrap, Wrap, WrapWithPosition};
+
+    use super::*;
+
+    #[test]
+    fn ligature() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = RustybuzzMeasure::new(&font_face);
+
+        // Roboto has a ligature for "ffi"
+        let glyphs = measure.shape("office");
+        let clusters: Vec<usize> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, vec![0, 1, 4, 5]);
+
+        assert_eq!(measure.str("office"), 1168 + 1748 + 1072 + 1085);
+        assert!(
+            measure.str("office") < measure.str("o") + 3 * measure.str("f") + measure.str("ice")
+        );
+    }
+
+    #[test]
+    fn wrap() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = RustybuzzMeasure::new(&font_face);
+
+        let word_wrap = ShapingWordWrap::new(10000, &measure);
+
+        let lines: Vec<&str> = "an office affix".wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["an office", "affix"]);
+    }
+
+    #[test]
+    fn glyph_positions() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = RustybuzzMeasure::new(&font_face);
+
+        let word_wrap = ShapingWordWrap::new(10000, &measure);
+
+        let positions: Vec<GlyphPosition> =
+            "an office affix".wrap_with_position(&word_wrap).collect();
+
+        // "an", " " and "office"
+        assert_eq!(positions.iter().filter(|p| p.line == 0).count(), 7);
+        assert_eq!(
+            positions[4],
+            GlyphPosition {
+                glyph_id: GlyphId(446),
+                cluster: 4..7,
+                line: 0,
+                offset: measure.str("an o"),
+                width: 1748,
+                x_offset: 0,
+                y_offset: 0,
+            }
+        );
+
+        // "affix" is on the next line, with an "ffi" ligature
+        let clusters: Vec<_> = positions[7..].iter().map(|p| p.cluster.clone()).collect();
+        assert_eq!(clusters, vec![10..11, 11..14, 14..15]);
+        assert!(positions[7..].iter().all(|p| p.line == 1));
+    }
+
+    #[test]
+    fn soft_hyphen() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = RustybuzzMeasure::new(&font_face);
+
+        let word_wrap = ShapingWordWrap::new(6000, &measure);
+
+        let text = "co\u{AD}op\u{AD}er";
+        let positions: Vec<GlyphPosition> = text.wrap_with_position(&word_wrap).collect();
+
+        // The hyphen is drawn for the soft hyphen the line breaks at
+        let hyphen = &positions[4];
+        assert_eq!(hyphen.cluster, 6..8);
+        assert_eq!(hyphen.line, 0);
+        assert_eq!(hyphen.width, i32::from(measure.char('-').unwrap()));
+        assert!(positions[5..].iter().all(|p| p.line == 1));
+    }
+}
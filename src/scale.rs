@@ -0,0 +1,273 @@
+use ttf_parser::Face;
+
+use crate::{CharPosition, Position, UnknownChar};
+
+/// How scaled values are rounded.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Rounding {
+    /// Values are not rounded.
+    Exact,
+
+    /// Values are rounded to the nearest whole pixel.
+    Round,
+
+    /// Values are rounded down to a whole pixel.
+    Floor,
+
+    /// Values are rounded up to a whole pixel.
+    Ceil,
+}
+
+impl Rounding {
+    fn apply(self, value: f32) -> f32 {
+        match self {
+            Rounding::Exact => value,
+            Rounding::Round => value.round(),
+            Rounding::Floor => value.floor(),
+            Rounding::Ceil => value.ceil(),
+        }
+    }
+}
+
+/// Converts between font units and pixels for a font size.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Scale {
+    units_per_em: u16,
+    pixels_per_em: f32,
+    rounding: Rounding,
+}
+
+impl Scale {
+    /// Creates a new `Scale` for a font with `units_per_em`, drawn `pixels_per_em` pixels high.
+    pub fn new(units_per_em: u16, pixels_per_em: f32) -> Self {
+        Self {
+            units_per_em,
+            pixels_per_em,
+            rounding: Rounding::Exact,
+        }
+    }
+
+    /// Creates a new `Scale` for `face` drawn `pixels_per_em` pixels high.
+    pub fn from_face(face: &Face<'_>, pixels_per_em: f32) -> Self {
+        Self::new(face.units_per_em(), pixels_per_em)
+    }
+
+    /// Creates a new `Scale` for `face` at a `point_size` on a display with `dpi` pixels per inch.
+    pub fn from_points(face: &Face<'_>, point_size: f32, dpi: f32) -> Self {
+        Self::from_face(face, point_size * dpi / 72.0)
+    }
+
+    /// Sets how pixel values are rounded, `Rounding::Exact` by default.
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// The number of pixels in one font unit.
+    pub fn factor(&self) -> f32 {
+        self.pixels_per_em / f32::from(self.units_per_em)
+    }
+
+    /// Converts font `units` to pixels, rounding the result.
+    pub fn to_pixels(&self, units: i32) -> f32 {
+        self.rounding.apply(units as f32 * self.factor())
+    }
+
+    /// Converts `pixels` to font units, such as a `max_width` for wrapping.
+    ///
+    /// The result is rounded down, so that the wrapped lines are never wider than `pixels`.
+    pub fn to_units(&self, pixels: f32) -> u32 {
+        let units = (pixels / self.factor()).floor();
+        if units.is_finite() && units > 0.0 {
+            units as u32
+        } else {
+            0
+        }
+    }
+}
+
+/// A `char`s position in lines of text, in pixels
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ScaledPosition {
+    /// The position in font units, with the char, its line and what measured it
+    pub position: Position,
+
+    /// The horizontal offset in pixels
+    pub offset: f32,
+
+    /// The width of the char in pixels
+    pub width: f32,
+}
+
+impl Position {
+    /// Converts the position from font units to pixels.
+    ///
+    /// When rounding, the left and right edges of the glyph are rounded so that adjacent glyphs
+    /// do not overlap or leave gaps.
+    pub fn scale(&self, scale: &Scale) -> ScaledPosition {
+        let left = self.offset as i32;
+        let right = left + i32::from(self.width);
+
+        let offset = scale.to_pixels(left);
+        ScaledPosition {
+            position: *self,
+            offset,
+            width: scale.to_pixels(right) - offset,
+        }
+    }
+}
+
+/// The position of a char in pixels, if known.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ScaledCharPosition {
+    /// The position of `char` is known.
+    Known(ScaledPosition),
+
+    /// The position of `char` is not known because `Measure` did not known it's size.
+    Unknown(UnknownChar),
+}
+
+impl CharPosition {
+    /// Converts the position from font units to pixels.
+    pub fn scale(&self, scale: &Scale) -> ScaledCharPosition {
+        match self {
+            CharPosition::Known(position) => ScaledCharPosition::Known(position.scale(scale)),
+            CharPosition::Unknown(unknown) => ScaledCharPosition::Unknown(*unknown),
+        }
+    }
+}
+
+/// Provides `.scale()` on iterators of `CharPosition`s
+pub trait ScalePositions: Iterator<Item = CharPosition> + Sized {
+    /// Converts each position from font units to pixels using `scale`.
+    fn scale(self, scale: Scale) -> ScaledPositionIterator<Self> {
+        ScaledPositionIterator {
+            scale,
+            positions: self,
+        }
+    }
+}
+
+impl<T> ScalePositions for T where T: Iterator<Item = CharPosition> {}
+
+/// Provides `ScaledCharPosition`s in pixels
+#[derive(Clone, Debug)]
+pub struct ScaledPositionIterator<T> {
+    scale: Scale,
+    positions: T,
+}
+
+impl<T> Iterator for ScaledPositionIterator<T>
+where
+    T: Iterator<Item = CharPosition>,
+{
+    type Item = ScaledCharPosition;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.positions
+            .next()
+            .map(|position| position.scale(&self.scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::GlyphId;
+
+    use crate::{TTFParserMeasure, WhiteSpaceWordWrap, Wrap, WrapWithPosition};
+
+    use super::*;
+
+    #[test]
+    fn units() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+
+        // Roboto has 2048 units per em
+        let scale = Scale::from_face(&font_face, 20.48);
+        assert!((scale.factor() - 0.01).abs() < f32::EPSILON);
+        assert_eq!(scale.to_units(200.0), 20000);
+        assert_eq!(scale.to_units(-1.0), 0);
+
+        // 12pt at 96 dpi is 16px
+        let scale = Scale::from_points(&font_face, 12.0, 96.0);
+        assert_eq!(scale, Scale::new(2048, 16.0));
+        assert_eq!(scale.to_pixels(2048), 16.0);
+    }
+
+    #[test]
+    fn rounding() {
+        let scale = Scale::new(1000, 10.0);
+        assert_eq!(scale.to_pixels(1234), 12.34);
+
+        assert_eq!(scale.with_rounding(Rounding::Round).to_pixels(1250), 13.0);
+        assert_eq!(scale.with_rounding(Rounding::Floor).to_pixels(1290), 12.0);
+        assert_eq!(scale.with_rounding(Rounding::Ceil).to_pixels(1210), 13.0);
+    }
+
+    #[test]
+    fn max_width() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let scale = Scale::from_face(&font_face, 20.48);
+        let word_wrap = WhiteSpaceWordWrap::new(scale.to_units(200.0), &measure);
+
+        let lines: Vec<&str> = "Mary had a little lamb whose fleece was white as snow"
+            .wrap(&word_wrap)
+            .collect();
+        assert_eq!(lines[0], "Mary had a little lamb");
+    }
+
+    #[test]
+    fn positions() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let scale = Scale::from_face(&font_face, 20.48).with_rounding(Rounding::Round);
+        let word_wrap = WhiteSpaceWordWrap::new(scale.to_units(200.0), &measure);
+
+        let positions: Vec<ScaledCharPosition> =
+            "Mary".wrap_with_position(&word_wrap).scale(scale).collect();
+
+        // 'M' is 1788 units wide, 'a' is 1114
+        assert_eq!(
+            positions[0],
+            ScaledCharPosition::Known(ScaledPosition {
+                position: Position {
+                    ch: 'M',
+                    index: 0,
+                    char_index: 0,
+                    line: 0,
+                    offset: 0,
+                    width: 1788,
+                    span: None,
+                    face: None,
+                    glyph_id: Some(GlyphId(49)),
+                },
+                offset: 0.0,
+                width: 18.0,
+            })
+        );
+        assert_eq!(
+            positions[1],
+            ScaledCharPosition::Known(ScaledPosition {
+                position: Position {
+                    ch: 'a',
+                    index: 1,
+                    char_index: 1,
+                    line: 0,
+                    offset: 1788,
+                    width: 1114,
+                    span: None,
+                    face: None,
+                    glyph_id: Some(GlyphId(69)),
+                },
+                offset: 18.0,
+                width: 11.0,
+            })
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use ttf_parser::GlyphId;
+
+use crate::{
+    token::{TokenKind, HYPHEN},
+    Measure,
+};
+
+/// A glyph produced by shaping a run of text.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Glyph {
+    /// The glyph in the font
+    pub glyph_id: GlyphId,
+
+    /// The byte index, in the shaped text, of the first char of the cluster this glyph belongs to
+    pub cluster: usize,
+
+    /// How far to advance after drawing the glyph
+    pub x_advance: i32,
+
+    /// Horizontal offset from the advance position to draw the glyph at
+    pub x_offset: i32,
+
+    /// Vertical offset from the baseline to draw the glyph at
+    pub y_offset: i32,
+}
+
+/// Implementing this allows text to be shaped into glyphs, instead of mapping one `char` to one
+/// glyph.
+///
+/// `Measure::str()` should return the sum of the `x_advance`s of the shaped glyphs.
+pub trait Shape: Measure {
+    /// Shapes `text` into glyphs, in visual order.
+    fn shape(&self, text: &str) -> Vec<Glyph>;
+}
+
+pub trait ShapeTokens<T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn shape_tokens<'a>(self, text: &'a str, shape: &'a dyn Shape) -> ShapedTokensIterator<'a, T>;
+}
+
+impl<T> ShapeTokens<T> for T
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn shape_tokens<'a>(self, text: &'a str, shape: &'a dyn Shape) -> ShapedTokensIterator<'a, T> {
+        ShapedTokensIterator {
+            text,
+            shape,
+            tokens: self,
+        }
+    }
+}
+
+/// Measures whole tokens by shaping them.
+///
+/// Graphemes are measured one at a time, so the width of a token does not include the
+/// substitutions and positioning between its graphemes until the token is shaped as a whole.
+#[derive(Clone)]
+pub struct ShapedTokensIterator<'a, T> {
+    text: &'a str,
+    shape: &'a dyn Shape,
+    tokens: T,
+}
+
+impl<'a, T> std::fmt::Debug for ShapedTokensIterator<'a, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShapedTokensIterator").finish()
+    }
+}
+
+impl<'a, T> Iterator for ShapedTokensIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    type Item = TokenKind;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token_kind = self.tokens.next()?;
+
+        let token_kind = match token_kind {
+            TokenKind::Required(mut token) | TokenKind::Optional(mut token) => {
+                token.display_width = self.shape.str(token.as_str(self.text));
+                token_kind.kind().token(token)
+            }
+            TokenKind::SoftHyphen(mut token) => {
+                token.display_width = self.shape.char(HYPHEN).map_or(0, u32::from);
+                TokenKind::SoftHyphen(token)
+            }
+            newline @ TokenKind::Newline(_) => newline,
+        };
+
+        Some(token_kind)
+    }
+}
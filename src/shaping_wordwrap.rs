@@ -0,0 +1,91 @@
+use crate::{
+    glyph_position::{GlyphPositionIterator, GlyphPositions},
+    grapheme_width::{GraphemeWidthIterator, WithGraphemeWidth},
+    line::{LineIterator, Lines},
+    line_break::{AddNewlines, LineBreakIterator},
+    metrics::{Metrics, MetricsIterator},
+    partial_tokens::{PartialTokensIterator, WithPartialTokens},
+    shaping::{Shape, ShapeTokens, ShapedTokensIterator},
+    whitespace::{TokenizeWhiteSpace, WhiteSpaceIterator},
+    wordwrap::{WordWrap, WordWrapWithMetrics, WordWrapWithPosition},
+    Measure, VerticalMetrics,
+};
+
+/// WordWrap for text that needs shaping, such as Arabic, Devanagari or text with ligatures.
+///
+/// Lines are split on whitespace like `WhiteSpaceWordWrap`, but each token is measured by shaping
+/// it as a whole. `wrap_with_position()` provides a `GlyphPosition` for each shaped glyph.
+#[derive(Debug)]
+pub struct ShapingWordWrap<'fnt> {
+    max_width: u32,
+    measure: &'fnt dyn Measure,
+    shape: &'fnt dyn Shape,
+    vertical_metrics: Option<VerticalMetrics>,
+}
+
+impl<'fnt> ShapingWordWrap<'fnt> {
+    /// Creates a new `ShapingWordWrap`
+    ///
+    /// Will wrap at `max_width` and shape the glyphs using `shape`
+    pub fn new<S: Shape>(max_width: u32, shape: &'fnt S) -> Self {
+        Self {
+            max_width,
+            measure: shape,
+            shape,
+            vertical_metrics: None,
+        }
+    }
+
+    /// Places the lines of `wrap_with_metrics()` vertically using `vertical_metrics`.
+    pub fn with_vertical_metrics(mut self, vertical_metrics: VerticalMetrics) -> Self {
+        self.vertical_metrics = Some(vertical_metrics);
+        self
+    }
+}
+
+type ShapedLineBreakIterator<'m> = LineBreakIterator<
+    PartialTokensIterator<
+        'm,
+        ShapedTokensIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>,
+    >,
+>;
+
+impl<'m, 'txt: 'm> WordWrap<'m, 'txt> for ShapingWordWrap<'m> {
+    type Iterator = LineIterator<'txt, ShapedLineBreakIterator<'m>>;
+
+    fn word_wrap(&self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .shape_tokens(text, self.shape)
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .lines(text)
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithPosition<'m, 'txt> for ShapingWordWrap<'m> {
+    type Iterator = GlyphPositionIterator<'m, ShapedLineBreakIterator<'m>>;
+
+    fn word_wrap_with_position(&'m self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .shape_tokens(text, self.shape)
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .glyph_positions(text, self.shape)
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithMetrics<'m, 'txt> for ShapingWordWrap<'m> {
+    type Iterator = MetricsIterator<ShapedLineBreakIterator<'m>>;
+
+    fn word_wrap_with_metrics(&'m self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .shape_tokens(text, self.shape)
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .metrics()
+            .with_vertical_metrics(self.vertical_metrics)
+    }
+}
@@ -0,0 +1,271 @@
+use std::ops::Range;
+
+use ttf_parser::GlyphId;
+
+use crate::{measure::is_kerned, Measure};
+
+/// A run of text measured with its own `Measure`, such as a bold, italic or code font.
+#[derive(Clone, Debug)]
+pub struct Span<'fnt> {
+    /// The bytes of the text in the span
+    pub range: Range<usize>,
+
+    /// Measures the glyphs of the span
+    pub measure: &'fnt dyn Measure,
+
+    /// Converts the units of `measure` to the units of the lines, e.g. 1.5 for text one and a
+    /// half times the size.
+    ///
+    /// Fonts with different units per em are converted with the ratio of their `Scale` factors.
+    pub scale: f32,
+}
+
+impl<'fnt> Span<'fnt> {
+    /// Creates a new `Span`
+    pub fn new(range: Range<usize>, measure: &'fnt dyn Measure, scale: f32) -> Self {
+        Self {
+            range,
+            measure,
+            scale,
+        }
+    }
+}
+
+/// Measures the text of each `Span` with the span's `Measure`.
+///
+/// Use it as the `Measure` of a wrapper and wrap the same `text`. Words that cross spans are not
+/// broken, and each `Position` has the index of its span. Text outside of the spans, and strings
+/// measured without a byte index, such as with `str()`, are measured with `measure`.
+#[derive(Clone, Debug)]
+pub struct SpanMeasure<'a> {
+    text: &'a str,
+    measure: &'a dyn Measure,
+    spans: Vec<Span<'a>>,
+}
+
+impl<'a> SpanMeasure<'a> {
+    /// Creates a new `SpanMeasure` for the `spans` of `text`.
+    ///
+    /// `spans` are in order and do not overlap.
+    pub fn new(text: &'a str, measure: &'a dyn Measure, spans: Vec<Span<'a>>) -> Self {
+        Self {
+            text,
+            measure,
+            spans,
+        }
+    }
+
+    /// The index of the span that byte `index` of the text is in.
+    fn find(&self, index: usize) -> Option<usize> {
+        let after = self.spans.partition_point(|span| span.range.start <= index);
+        after
+            .checked_sub(1)
+            .filter(|&before| self.spans[before].range.contains(&index))
+    }
+
+    /// The measure and scale of byte `index`, and where they end.
+    fn run_at(&self, index: usize) -> (&dyn Measure, f32, usize) {
+        match self.find(index) {
+            Some(span) => {
+                let span = &self.spans[span];
+                (span.measure, span.scale, span.range.end)
+            }
+            None => {
+                let next = self
+                    .spans
+                    .iter()
+                    .map(|span| span.range.start)
+                    .find(|&start| start > index)
+                    .unwrap_or(self.text.len());
+                (self.measure, 1.0, next)
+            }
+        }
+    }
+}
+
+impl<'a> Measure for SpanMeasure<'a> {
+    fn str(&self, text: &str) -> u32 {
+        self.measure.str(text)
+    }
+
+    fn str_at(&self, index: usize, text: &str) -> u32 {
+        // A char at a time, rounded like the positions are, so that they add up to the width
+        let mut previous = None;
+
+        text.char_indices().fold(0, |width: u32, (offset, c)| {
+            let index = index + offset;
+            let kerning = previous
+                .replace(c)
+                .filter(|&left| is_kerned(left, c))
+                .map_or(0, |left| self.kerning_at(index, left, c));
+            width
+                .saturating_add(u32::from(self.char_at(index, c).unwrap_or_default()))
+                .saturating_add_signed(i32::from(kerning))
+        })
+    }
+
+    fn char(&self, c: char) -> Option<u16> {
+        self.measure.char(c)
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        self.measure.kerning(left, right)
+    }
+
+    fn char_at(&self, index: usize, c: char) -> Option<u16> {
+        let (measure, scale, _) = self.run_at(index);
+        measure
+            .char(c)
+            .map(|width| (f32::from(width) * scale).round() as u16)
+    }
+
+    fn kerning_at(&self, index: usize, left: char, right: char) -> i16 {
+        // There is no kerning between spans
+        let left_index = index.saturating_sub(left.len_utf8());
+        if self.find(left_index) != self.find(index) {
+            return 0;
+        }
+
+        let (measure, scale, _) = self.run_at(index);
+        (f32::from(measure.kerning(left, right)) * scale).round() as i16
+    }
+
+    fn span_at(&self, index: usize) -> Option<usize> {
+        self.find(index)
+    }
+
+    fn face_at(&self, index: usize, c: char) -> Option<usize> {
+        let (measure, _, _) = self.run_at(index);
+        measure.face_at(index, c)
+    }
+
+    fn glyph(&self, c: char) -> Option<GlyphId> {
+        self.measure.glyph(c)
+    }
+
+    fn glyph_at(&self, index: usize, c: char) -> Option<GlyphId> {
+        let (measure, _, _) = self.run_at(index);
+        measure.glyph_at(index, c)
+    }
+
+    fn char_glyph_at(&self, index: usize, c: char) -> (Option<u16>, Option<GlyphId>) {
+        let (measure, scale, _) = self.run_at(index);
+        let (width, glyph_id) = measure.char_glyph_at(index, c);
+        let width = width.map(|width| (f32::from(width) * scale).round() as u16);
+        (width, glyph_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{CharPosition, TTFParserMeasure, WhiteSpaceWordWrap, Wrap, WrapWithPosition};
+
+    use super::*;
+
+    #[test]
+    fn str() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "small LARGE";
+        let span_measure = SpanMeasure::new(text, &measure, vec![Span::new(6..11, &measure, 2.0)]);
+
+        // Each span is measured on its own
+        assert_eq!(span_measure.str_at(0, &text[0..6]), measure.str("small "));
+        assert_eq!(span_measure.str_at(6, &text[6..]), measure.str("LARGE") * 2);
+        assert_eq!(
+            span_measure.str_at(0, text),
+            measure.str("small ") + measure.str("LARGE") * 2
+        );
+
+        // Without the byte index, there is no span
+        assert_eq!(span_measure.str(&text[6..]), measure.str("LARGE"));
+    }
+
+    #[test]
+    fn rounding() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "To To";
+        let span_measure = SpanMeasure::new(text, &measure, vec![Span::new(0..5, &measure, 1.3)]);
+
+        // The positions add up to the width of the line
+        let word_wrap = WhiteSpaceWordWrap::new(20000, &span_measure);
+        let last = text
+            .wrap_with_position(&word_wrap)
+            .filter_map(|position| match position {
+                CharPosition::Known(position) => Some(position),
+                CharPosition::Unknown(_) => None,
+            })
+            .last()
+            .unwrap();
+        assert_eq!(
+            last.offset + u32::from(last.width),
+            span_measure.str_at(0, text)
+        );
+    }
+
+    #[test]
+    fn wrap() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "some words some words";
+        let width = measure.str("some words some");
+
+        // Without spans, three words fit on the first line
+        let word_wrap = WhiteSpaceWordWrap::new(width, &measure);
+        let lines: Vec<&str> = text.wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["some words some", "words"]);
+
+        // The larger span pushes a word onto the next line
+        let span_measure = SpanMeasure::new(text, &measure, vec![Span::new(5..10, &measure, 1.5)]);
+        let word_wrap = WhiteSpaceWordWrap::new(width, &span_measure);
+        let lines: Vec<&str> = text.wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["some words", "some words"]);
+    }
+
+    #[test]
+    fn across_spans() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // "bold" is partly in a span, it stays one word
+        let text = "a bold word";
+        let spans = vec![Span::new(2..4, &measure, 1.5)];
+        let span_measure = SpanMeasure::new(text, &measure, spans);
+
+        let width = measure.str("a bold") + 1;
+        let word_wrap = WhiteSpaceWordWrap::new(width, &span_measure);
+        let lines: Vec<&str> = text.wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["a", "bold", "word"]);
+
+        // Positions have the span of their char
+        let positions: Vec<(char, Option<usize>, u16)> = text
+            .wrap_with_position(&word_wrap)
+            .filter_map(|position| match position {
+                CharPosition::Known(position) => Some((position.ch, position.span, position.width)),
+                CharPosition::Unknown(_) => None,
+            })
+            .take(4)
+            .collect();
+
+        let scaled = |c| (f32::from(measure.char(c).unwrap()) * 1.5).round() as u16;
+        assert_eq!(
+            positions,
+            vec![
+                ('a', None, measure.char('a').unwrap()),
+                ('b', Some(0), scaled('b')),
+                ('o', Some(0), scaled('o')),
+                ('l', None, measure.char('l').unwrap()),
+            ]
+        );
+    }
+}
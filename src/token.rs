@@ -2,11 +2,18 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use crate::measure::Measure;
 
+/// An invisible hyphen that marks where a word may be broken.
+pub const SOFT_HYPHEN: &str = "\u{AD}";
+
+/// The hyphen that is drawn when a line breaks at a soft hyphen.
+pub const HYPHEN: char = '-';
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Kind {
     Required,
     Optional,
     Newline,
+    SoftHyphen,
 }
 
 impl Kind {
@@ -15,6 +22,7 @@ impl Kind {
             Kind::Required => TokenKind::Required(token),
             Kind::Optional => TokenKind::Optional(token),
             Kind::Newline => TokenKind::Newline(Some(token)),
+            Kind::SoftHyphen => TokenKind::SoftHyphen(token),
         }
     }
 }
@@ -23,6 +31,7 @@ impl Kind {
 ///
 /// `Optional` tokens may be omitted from lines if they occur at the beginning or end of a line.
 /// `Newline` tokens cause a newline.
+/// `SoftHyphen` tokens are omitted unless the line breaks at them, then a hyphen is drawn.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum TokenKind {
     /// The token must be used.
@@ -33,6 +42,9 @@ pub enum TokenKind {
 
     /// The token causes a newline
     Newline(Option<Token>),
+
+    /// The line may break at the token, its width is the width of the hyphen that is drawn.
+    SoftHyphen(Token),
 }
 
 impl TokenKind {
@@ -48,18 +60,25 @@ impl TokenKind {
         matches!(self, TokenKind::Newline(_))
     }
 
+    pub fn is_soft_hyphen(&self) -> bool {
+        matches!(self, TokenKind::SoftHyphen(_))
+    }
+
     pub fn width(&self) -> u32 {
         match self {
             TokenKind::Required(token) => token.display_width,
             TokenKind::Optional(token) => token.display_width,
             TokenKind::Newline(Some(token)) => token.display_width,
             TokenKind::Newline(None) => 0,
+            TokenKind::SoftHyphen(token) => token.display_width,
         }
     }
 
     pub fn into_token(self) -> Option<Token> {
         match self {
-            TokenKind::Required(token) | TokenKind::Optional(token) => Some(token),
+            TokenKind::Required(token)
+            | TokenKind::Optional(token)
+            | TokenKind::SoftHyphen(token) => Some(token),
             TokenKind::Newline(token) => token,
         }
     }
@@ -69,6 +88,7 @@ impl TokenKind {
             TokenKind::Required(_) => Kind::Required,
             TokenKind::Optional(_) => Kind::Optional,
             TokenKind::Newline(_) => Kind::Newline,
+            TokenKind::SoftHyphen(_) => Kind::SoftHyphen,
         }
     }
 }
@@ -98,7 +118,7 @@ impl Token {
 
     /// Creates a new Token
     pub fn measure(text: &str, measure: &dyn Measure) -> Token {
-        let display_width = measure.str(&text);
+        let display_width = measure.str(text);
         Self {
             start: 0,
             end: text.len(),
@@ -113,36 +133,7 @@ impl Token {
         text: &str,
         measure: &dyn Measure,
     ) -> (Option<Token>, Option<Token>) {
-        // Our little slice of the world
-        let slice = &text[self.start..self.end];
-
-        let index = slice.graphemes(true).take(graphemes).map(|s| s.len()).sum();
-
-        self.split_at(index, text, measure)
-    }
-
-    pub fn split_at(
-        &self,
-        index: usize,
-        text: &str,
-        measure: &dyn Measure,
-    ) -> (Option<Token>, Option<Token>) {
-        let index = self.start + index;
-
-        let mut head = self.clone();
-        head.end = index;
-        head.display_width = measure.str(&text[head.start..head.end]);
-
-        let mut tail = self.clone();
-        tail.start = index;
-        tail.display_width = measure.str(&text[tail.start..tail.end]);
-
-        match (head.start == head.end, tail.start == tail.end) {
-            (true, true) => (None, None),
-            (false, true) => (Some(head), None),
-            (true, false) => (None, Some(tail)),
-            (false, false) => (Some(head), Some(tail)),
-        }
+        self.split_while(text, measure, |count, _| count < graphemes)
     }
 
     /// Subdivides the token at `display_width`.
@@ -159,28 +150,68 @@ impl Token {
 
         // Optimize the case where the token fits in the display_width
         if self.display_width < display_width {
-            return (Some(self.clone()), None);
+            return (Some(*self), None);
         }
 
-        let mut head_width: u32 = 0;
-        let mut index = 0;
-
-        let mut chars = text[self.start..self.end].chars();
-
-        while let Some(ch) = chars.next() {
-            // char widths that are not known will be placed on the same line
-            let ch_display_width = measure.char(ch).unwrap_or_default();
-            let next_width = head_width + u32::from(ch_display_width);
+        self.split_while(text, measure, |_, width| width <= display_width)
+    }
 
-            if next_width > display_width {
+    /// Subdivides the token after the graphemes that `fits`, which is given the number of graphemes
+    /// in the head so far and the width of the head with the next grapheme.
+    ///
+    /// Only the graphemes of the head are measured, the width of the tail is the rest of the width
+    /// of the token. Splitting a long token into lines is then linear in its length.
+    fn split_while<F>(
+        &self,
+        text: &str,
+        measure: &dyn Measure,
+        mut fits: F,
+    ) -> (Option<Token>, Option<Token>)
+    where
+        F: FnMut(usize, u32) -> bool,
+    {
+        let mut head_width: u32 = 0;
+        let mut index = self.start;
+        let mut previous = None;
+
+        // The kerning between the head and the tail is not part of either
+        let mut kerning = 0;
+
+        let graphemes = text[self.start..self.end].grapheme_indices(true);
+        for (count, (offset, grapheme)) in graphemes.enumerate() {
+            let grapheme_index = self.start + offset;
+            let grapheme_kerning = match (previous, grapheme.chars().next()) {
+                (Some(left), Some(right)) => measure.kerning_at(grapheme_index, left, right),
+                _ => 0,
+            };
+            let width = head_width
+                .saturating_add(measure.str_at(grapheme_index, grapheme))
+                .saturating_add_signed(i32::from(grapheme_kerning));
+
+            if !fits(count, width) {
+                kerning = grapheme_kerning;
                 break;
             }
 
-            head_width = next_width;
-            index += ch.len_utf8();
+            head_width = width;
+            index = grapheme_index + grapheme.len();
+            previous = grapheme.chars().next_back();
         }
 
-        self.split_at(index, text, measure)
+        let tail_width = self
+            .display_width
+            .saturating_sub(head_width)
+            .saturating_add_signed(-i32::from(kerning));
+
+        let head = Token::new(self.start, index, head_width);
+        let tail = Token::new(index, self.end, tail_width);
+
+        match (head.start == head.end, tail.start == tail.end) {
+            (true, true) => (None, None),
+            (false, true) => (Some(head), None),
+            (true, false) => (None, Some(tail)),
+            (false, false) => (Some(head), Some(tail)),
+        }
     }
 
     pub fn as_str(self, text: &str) -> &str {
@@ -222,7 +253,7 @@ mod tests {
         // the number 1 is much larger than the display width of 100
         let (head, tail) = token.split_at_width(100, text, &measure);
         assert!(head.is_none());
-        assert_eq!("1234567890", tail.unwrap().as_str(&text));
+        assert_eq!("1234567890", tail.unwrap().as_str(text));
     }
 
     #[test]
@@ -237,6 +268,72 @@ mod tests {
         let (head, tail) = token.split_at_width(6000, text, &measure);
         assert_eq!("12345", head.unwrap().as_str(text));
         assert_eq!("67890", tail.unwrap().as_str(text));
+
+        // The tail is as wide as the rest of the token
+        assert_eq!(head.unwrap().display_width, measure.str("12345"));
+        assert_eq!(tail.unwrap().display_width, measure.str("67890"));
+    }
+
+    #[test]
+    fn split_kerning() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // "T" and "o" are kerned, the kerning is lost when they are split
+        let text = "ToTo";
+        let token = Token::measure(text, &measure);
+
+        let (head, tail) = token.split_at_grapheme(3, text, &measure);
+        assert_eq!(head.unwrap().display_width, measure.str("ToT"));
+        assert_eq!(tail.unwrap().display_width, measure.str("o"));
+    }
+
+    /// Counts the bytes that are measured
+    #[derive(Debug)]
+    struct Counting<'a> {
+        measure: &'a dyn Measure,
+        bytes: std::cell::Cell<usize>,
+    }
+
+    impl<'a> Measure for Counting<'a> {
+        fn str(&self, text: &str) -> u32 {
+            self.bytes.set(self.bytes.get() + text.len());
+            self.measure.str(text)
+        }
+
+        fn char(&self, c: char) -> Option<u16> {
+            self.bytes.set(self.bytes.get() + c.len_utf8());
+            self.measure.char(c)
+        }
+    }
+
+    #[test]
+    fn linear() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let counting = Counting {
+            measure: &measure,
+            bytes: Default::default(),
+        };
+
+        // A long token is split into many lines
+        let text = "0123456789".repeat(1000);
+        let mut token = Token::measure(&text, &counting);
+        let mut lines = 0;
+        while let (Some(_), tail) = token.split_at_width(10_000, &text, &counting) {
+            lines += 1;
+            match tail {
+                Some(tail) => token = tail,
+                None => break,
+            }
+        }
+        assert_eq!(lines, 1250);
+
+        // Each byte is measured when the token is, and about once more when it is split off,
+        // instead of each time a line is split off before it
+        assert!(counting.bytes.get() < text.len() * 3);
     }
 
     #[test]
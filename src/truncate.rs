@@ -0,0 +1,368 @@
+use std::{borrow::Cow, collections::VecDeque, fmt::Formatter, iter::Once};
+
+use crate::{
+    line::LineIterator,
+    metrics::{LineBreak, LineBreaks},
+    position::{PositionIterator, Positions},
+    token::{Token, TokenKind},
+    CharPosition, Measure,
+};
+
+/// Where the ellipsis goes after the text was truncated
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Ellipsis {
+    /// The line that ends with the ellipsis
+    pub line: u32,
+
+    /// The horizontal offset of the ellipsis, before the line is aligned
+    pub offset: u32,
+
+    /// The byte index in the text where it was truncated
+    pub index: usize,
+}
+
+/// A token stream that may have been truncated.
+pub trait Truncated {
+    /// Where the ellipsis goes, if the text was truncated.
+    fn ellipsis(&self) -> Option<Ellipsis>;
+}
+
+pub trait Truncate<T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn truncate<'a>(
+        self,
+        max_lines: u32,
+        max_width: u32,
+        ellipsis_width: u32,
+        text: &'a str,
+        measure: &'a dyn Measure,
+    ) -> TruncateIterator<'a, T>;
+}
+
+impl<T> Truncate<T> for T
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn truncate<'a>(
+        self,
+        max_lines: u32,
+        max_width: u32,
+        ellipsis_width: u32,
+        text: &'a str,
+        measure: &'a dyn Measure,
+    ) -> TruncateIterator<'a, T> {
+        TruncateIterator {
+            max_lines,
+            max_width,
+            ellipsis_width,
+            text,
+            measure,
+            tokens: self,
+            line: 0,
+            pending: VecDeque::new(),
+            ellipsis: None,
+        }
+    }
+}
+
+/// Limits the token stream to `max_lines` lines.
+///
+/// If there is more text, the last line is shortened to leave room for the ellipsis. Words that
+/// do not fit before the ellipsis are dropped, a word is only broken if it is the only one.
+#[derive(Clone)]
+pub struct TruncateIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    max_lines: u32,
+    max_width: u32,
+    ellipsis_width: u32,
+    text: &'a str,
+    measure: &'a dyn Measure,
+    tokens: T,
+
+    /// The line of the next token
+    line: u32,
+
+    /// The tokens of the last line
+    pending: VecDeque<TokenKind>,
+
+    ellipsis: Option<Ellipsis>,
+}
+
+impl<'a, T> std::fmt::Debug for TruncateIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TruncateIterator")
+            .field("max_lines", &self.max_lines)
+            .field("ellipsis", &self.ellipsis)
+            .finish()
+    }
+}
+
+impl<'a, T> TruncateIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    /// Reads the last line, and shortens it if there is more text after it.
+    fn last_line(&mut self) {
+        let mut tokens = Vec::new();
+        for token_kind in self.tokens.by_ref() {
+            tokens.push(token_kind);
+            if token_kind.is_newline() {
+                break;
+            }
+        }
+
+        if self.tokens.next().is_none() {
+            // All of the text fits
+            self.pending.extend(tokens);
+            return;
+        }
+
+        let available = self.max_width.saturating_sub(self.ellipsis_width);
+        let mut width = 0;
+        let mut index = tokens
+            .first()
+            .and_then(|token_kind| token_kind.into_token())
+            .map_or(0, |token| token.start);
+
+        for token_kind in tokens {
+            let token = match token_kind {
+                TokenKind::Required(token) | TokenKind::Optional(token) => token,
+                // The ellipsis replaces the hyphen and the newline
+                TokenKind::SoftHyphen(_) | TokenKind::Newline(_) => break,
+            };
+
+            if width + token.display_width <= available {
+                width += token.display_width;
+                index = token.end;
+                self.pending.push_back(token_kind);
+                continue;
+            }
+
+            // The line ends at the last break opportunity that leaves room for the ellipsis, a
+            // word is only broken if there is none
+            let has_break = self
+                .pending
+                .iter()
+                .any(|token_kind| token_kind.is_required());
+            if token_kind.is_required() && !has_break {
+                let (head, _) = token.split_at_width(available - width, self.text, self.measure);
+                if let Some(head) = head {
+                    width += head.display_width;
+                    index = head.end;
+                    self.pending.push_back(TokenKind::Required(head));
+                }
+            }
+            break;
+        }
+
+        // Whitespace before the ellipsis is removed
+        while let Some(TokenKind::Optional(token)) = self.pending.back() {
+            width -= token.display_width;
+            index = token.start;
+            self.pending.pop_back();
+        }
+
+        // The ellipsis takes up space at the end of the line, so that it is aligned with it
+        self.pending.push_back(TokenKind::Required(Token {
+            start: index,
+            end: index,
+            display_width: self.ellipsis_width,
+        }));
+
+        self.ellipsis.replace(Ellipsis {
+            line: self.line,
+            offset: width,
+            index,
+        });
+    }
+}
+
+impl<'a, T> Iterator for TruncateIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    type Item = TokenKind;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.line + 1 < self.max_lines {
+            let token_kind = self.tokens.next()?;
+            if token_kind.is_newline() {
+                self.line += 1;
+            }
+            return Some(token_kind);
+        }
+
+        if self.line + 1 == self.max_lines {
+            // No more lines after this one
+            self.last_line();
+            self.line += 1;
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+impl<'a, T> Truncated for TruncateIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn ellipsis(&self) -> Option<Ellipsis> {
+        self.ellipsis
+    }
+}
+
+impl<'a, T> LineBreaks for TruncateIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind> + LineBreaks,
+{
+    fn line_break(&self) -> LineBreak {
+        self.tokens.line_break()
+    }
+
+    fn wrapped_white_space(&self) -> u32 {
+        self.tokens.wrapped_white_space()
+    }
+}
+
+/// The ellipsis is positioned as a single token, which is never wrapped.
+impl LineBreaks for Once<TokenKind> {
+    fn line_break(&self) -> LineBreak {
+        LineBreak::EndOfText
+    }
+
+    fn wrapped_white_space(&self) -> u32 {
+        0
+    }
+}
+
+/// Provides lines as `Cow<str>`, the last line ends with the ellipsis if the text was truncated
+#[derive(Clone)]
+pub struct EllipsisLineIterator<'a, T> {
+    ellipsis: &'a str,
+    lines: LineIterator<'a, T>,
+    done: bool,
+}
+
+impl<'a, T> EllipsisLineIterator<'a, T> {
+    pub fn new(lines: LineIterator<'a, T>, ellipsis: &'a str) -> Self {
+        Self {
+            ellipsis,
+            lines,
+            done: false,
+        }
+    }
+}
+
+impl<'a, T> std::fmt::Debug for EllipsisLineIterator<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EllipsisLineIterator")
+            .field("ellipsis", &self.ellipsis)
+            .finish()
+    }
+}
+
+impl<'a, T> Iterator for EllipsisLineIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind> + Truncated,
+{
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let line = self.lines.next();
+        if self.lines.tokens().ellipsis().is_some() {
+            // The text was truncated, this is the last line
+            self.done = true;
+            let line = line.unwrap_or_default();
+            return Some(Cow::Owned(format!("{}{}", line, self.ellipsis)));
+        }
+
+        line.map(Cow::Borrowed)
+    }
+}
+
+/// Provides `CharPosition`s, followed by the ellipsis if the text was truncated
+#[derive(Clone)]
+pub struct EllipsisPositionIterator<'a, T> {
+    ellipsis: &'a str,
+    measure: &'a dyn Measure,
+    positions: PositionIterator<'a, T>,
+
+    /// The positions of the ellipsis, once the text has run out, with the char index and the
+    /// aligned offset it is at
+    ellipsis_positions: Option<(Ellipsis, usize, u32, PositionIterator<'a, Once<TokenKind>>)>,
+}
+
+impl<'a, T> EllipsisPositionIterator<'a, T> {
+    pub fn new(
+        positions: PositionIterator<'a, T>,
+        ellipsis: &'a str,
+        measure: &'a dyn Measure,
+    ) -> Self {
+        Self {
+            ellipsis,
+            measure,
+            positions,
+            ellipsis_positions: None,
+        }
+    }
+}
+
+impl<'a, T> std::fmt::Debug for EllipsisPositionIterator<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EllipsisPositionIterator")
+            .field("ellipsis", &self.ellipsis)
+            .finish()
+    }
+}
+
+impl<'a, T> Iterator for EllipsisPositionIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind> + Truncated + LineBreaks,
+{
+    type Item = CharPosition;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ellipsis_positions.is_none() {
+            if let Some(position) = self.positions.next() {
+                return Some(position);
+            }
+
+            // Measure the ellipsis as a token of its own
+            let ellipsis = self.positions.tokens().ellipsis()?;
+            let token = TokenKind::Required(Token::measure(self.ellipsis, self.measure));
+            let positions = std::iter::once(token).positions(self.ellipsis, self.measure);
+            let char_index = self.positions.char_index(ellipsis.index);
+            let offset = self.positions.offset();
+            self.ellipsis_positions
+                .replace((ellipsis, char_index, offset, positions));
+        }
+
+        let (ellipsis, char_index, offset, positions) = self.ellipsis_positions.as_mut()?;
+        let mut position = positions.next()?;
+        match &mut position {
+            CharPosition::Known(position) => {
+                position.index = ellipsis.index;
+                position.char_index = *char_index;
+                position.line = ellipsis.line;
+                position.offset += *offset;
+            }
+            CharPosition::Unknown(unknown) => {
+                unknown.index = ellipsis.index;
+                unknown.char_index = *char_index;
+            }
+        }
+
+        Some(position)
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{
+    alignment::Alignment,
+    grapheme_width::{GraphemeWidthIterator, WithGraphemeWidth},
+    line::Lines,
+    line_break::{AddNewlines, LineBreakIterator},
+    line_widths::LineWidths,
+    partial_tokens::{PartialTokensIterator, WithPartialTokens},
+    position::Positions,
+    truncate::{EllipsisLineIterator, EllipsisPositionIterator, Truncate, TruncateIterator},
+    whitespace::{TokenizeWhiteSpace, WhiteSpaceIterator},
+    wordwrap::{WordWrap, WordWrapWithPosition},
+    Measure,
+};
+
+/// WordWrap for variable-width TTF text into a limited number of lines.
+///
+/// Lines are split on whitespace like `WhiteSpaceWordWrap`. If the text does not fit in
+/// `max_lines` lines, the last line is shortened and ends with an ellipsis, so that it still fits
+/// in `max_width`.
+///
+/// Every line is `max_width` wide, there are no per-line widths or indents.
+#[derive(Debug)]
+pub struct TruncatedWordWrap<'fnt> {
+    max_width: u32,
+    max_lines: u32,
+    measure: &'fnt dyn Measure,
+    ellipsis: &'fnt str,
+    alignment: Alignment,
+}
+
+impl<'fnt> TruncatedWordWrap<'fnt> {
+    /// Creates a new `TruncatedWordWrap`
+    ///
+    /// Will wrap at `max_width` into at most `max_lines` lines, and measure the glyphs using
+    /// `measure`. The ellipsis is "…" unless set with `with_ellipsis()`.
+    pub fn new(max_width: u32, max_lines: u32, measure: &'fnt dyn Measure) -> Self {
+        Self {
+            max_width,
+            max_lines,
+            measure,
+            ellipsis: "\u{2026}",
+            alignment: Alignment::Left,
+        }
+    }
+
+    /// Ends truncated text with `ellipsis`, such as "...".
+    pub fn with_ellipsis(mut self, ellipsis: &'fnt str) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    /// Places the lines of `wrap_with_position()` within `max_width` using `alignment`, along
+    /// with the ellipsis.
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+type TruncatedLineBreakIterator<'m> = TruncateIterator<
+    'm,
+    LineBreakIterator<PartialTokensIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>>,
+>;
+
+impl<'m, 'txt: 'm> WordWrap<'m, 'txt> for TruncatedWordWrap<'m> {
+    type Iterator = EllipsisLineIterator<'m, TruncatedLineBreakIterator<'m>>;
+
+    fn word_wrap(&'m self, text: &'txt str) -> Self::Iterator {
+        let lines = text
+            .with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .truncate(
+                self.max_lines,
+                self.max_width,
+                self.measure.str(self.ellipsis),
+                text,
+                self.measure,
+            )
+            .lines(text);
+
+        EllipsisLineIterator::new(lines, self.ellipsis)
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithPosition<'m, 'txt> for TruncatedWordWrap<'m> {
+    type Iterator = EllipsisPositionIterator<'m, TruncatedLineBreakIterator<'m>>;
+
+    fn word_wrap_with_position(&'m self, text: &'txt str) -> Self::Iterator {
+        let positions = text
+            .with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .truncate(
+                self.max_lines,
+                self.max_width,
+                self.measure.str(self.ellipsis),
+                text,
+                self.measure,
+            )
+            .positions(text, self.measure)
+            .aligned(self.alignment, LineWidths::new(self.max_width));
+
+        EllipsisPositionIterator::new(positions, self.ellipsis, self.measure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use ttf_parser::Face;
+
+    use crate::{CharPosition, Position, TTFParserMeasure, Wrap, WrapWithPosition};
+
+    use super::*;
+
+    #[test]
+    fn fits() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = TruncatedWordWrap::new(20000, 2, &measure);
+
+        let lines: Vec<Cow<'_, str>> = "Mary had\na little lamb\n".wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["Mary had", "a little lamb"]);
+        assert!(lines.iter().all(|line| matches!(line, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn truncated() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = TruncatedWordWrap::new(20000, 2, &measure);
+
+        let poem = "Mary had a little lamb whose fleece was white as snow";
+        let lines: Vec<Cow<'_, str>> = poem.wrap(&word_wrap).collect();
+        assert_eq!(
+            lines,
+            vec!["Mary had a little lamb", "whose fleece was\u{2026}"]
+        );
+
+        // Words are dropped if the ellipsis does not fit after them
+        let word_wrap = TruncatedWordWrap::new(20000, 1, &measure);
+        let lines: Vec<Cow<'_, str>> = poem.wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["Mary had a little\u{2026}"]);
+        assert!(measure.str("Mary had a little lam\u{2026}") <= 20000);
+    }
+
+    #[test]
+    fn broken_word() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // A word is only broken if it is the only one on the line
+        let word_wrap = TruncatedWordWrap::new(6000, 1, &measure);
+        let lines: Vec<Cow<'_, str>> = "1234567890 12".wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["1234\u{2026}"]);
+        assert!(measure.str(&lines[0]) <= 6000);
+    }
+
+    #[test]
+    fn newlines() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = TruncatedWordWrap::new(20000, 2, &measure).with_ellipsis("...");
+
+        let lines: Vec<Cow<'_, str>> = "a\nb\nc".wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["a", "b..."]);
+
+        let lines: Vec<Cow<'_, str>> = "a\n\nc".wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["a", "..."]);
+    }
+
+    #[test]
+    fn positions() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = TruncatedWordWrap::new(6000, 1, &measure);
+
+        let positions: Vec<CharPosition> = "12 34 56".wrap_with_position(&word_wrap).collect();
+
+        // "12" and the ellipsis, "34" is dropped
+        assert_eq!(positions.len(), 3);
+        let offset = measure.str("12");
+        assert_eq!(
+            positions[2],
+            CharPosition::Known(Position {
+                ch: '\u{2026}',
+                index: 2,
+                char_index: 2,
+                line: 0,
+                offset,
+                width: measure.char('\u{2026}').unwrap(),
+                span: None,
+                face: None,
+                glyph_id: font_face.glyph_index('\u{2026}'),
+            })
+        );
+        assert!(measure.str("12 34\u{2026}") > 6000);
+    }
+
+    #[test]
+    fn aligned() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = TruncatedWordWrap::new(6000, 1, &measure).with_alignment(Alignment::Right);
+
+        let positions: Vec<CharPosition> = "12 34 56".wrap_with_position(&word_wrap).collect();
+
+        // The line ends with the ellipsis at the max_width
+        let positions: Vec<Position> = positions
+            .iter()
+            .filter_map(|position| match position {
+                CharPosition::Known(position) => Some(*position),
+                CharPosition::Unknown(_) => None,
+            })
+            .collect();
+        let width = measure.str("12\u{2026}");
+        assert_eq!(positions[0].offset, 6000 - width);
+        let ellipsis = positions.last().unwrap();
+        assert_eq!(ellipsis.ch, '\u{2026}');
+        assert_eq!(ellipsis.offset + u32::from(ellipsis.width), 6000);
+    }
+
+    #[test]
+    fn no_lines() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = TruncatedWordWrap::new(20000, 0, &measure);
+
+        assert!("text".wrap(&word_wrap).next().is_none());
+        assert!("text".wrap_with_position(&word_wrap).next().is_none());
+    }
+}
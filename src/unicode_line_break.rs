@@ -0,0 +1,353 @@
+use std::{collections::VecDeque, iter::Peekable};
+
+use unicode_linebreak::{break_property, linebreaks, BreakClass, BreakOpportunity};
+
+use crate::{
+    grapheme_width::GraphemeWidth,
+    token::{Kind, Token, TokenKind, SOFT_HYPHEN},
+};
+
+/// Graphemes that end with a char of the UAX #14 classes BK, CR, LF or NL.
+///
+/// Only needed at the end of the text, where there is always a mandatory break.
+fn ends_with_hard_break(grapheme: &str) -> bool {
+    grapheme.chars().next_back().is_some_and(|last| {
+        matches!(
+            break_property(u32::from(last)),
+            BreakClass::Mandatory
+                | BreakClass::CarriageReturn
+                | BreakClass::LineFeed
+                | BreakClass::NextLine
+        )
+    })
+}
+
+fn is_white_space(grapheme: &str) -> bool {
+    matches!(grapheme.chars().next(), Some(first) if first.is_whitespace())
+}
+
+/// The width of `graphemes`, including the kerning between them.
+fn width(graphemes: &[GraphemeWidth<'_>]) -> u32 {
+    graphemes
+        .iter()
+        .enumerate()
+        .fold(0, |width: u32, (index, grapheme_width)| {
+            let kerning = if index == 0 {
+                0
+            } else {
+                grapheme_width.kerning
+            };
+            (width + grapheme_width.display_width).saturating_add_signed(i32::from(kerning))
+        })
+}
+
+pub trait TokenizeUnicodeLineBreak<'a, T>
+where
+    T: Iterator<Item = GraphemeWidth<'a>> + Clone,
+{
+    fn tokenize_unicode_line_break(self, text: &'a str) -> UnicodeLineBreakIterator<'a, T>;
+}
+
+impl<'a, T> TokenizeUnicodeLineBreak<'a, T> for T
+where
+    T: Iterator<Item = GraphemeWidth<'a>> + Clone,
+{
+    fn tokenize_unicode_line_break(self, text: &'a str) -> UnicodeLineBreakIterator<'a, T> {
+        UnicodeLineBreakIterator::new(self.peekable(), text)
+    }
+}
+
+/// Tokenizes text at the line break opportunities of the Unicode Line Breaking Algorithm.
+///
+/// The text between two break opportunities is split into a `Required` token, followed by a
+/// `SoftHyphen` token for a trailing soft hyphen, an `Optional` token for trailing whitespace and
+/// a `Newline` token for a trailing hard break.
+#[derive(Clone, Debug)]
+pub struct UnicodeLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = GraphemeWidth<'a>> + Clone,
+{
+    index: usize,
+    /// The length of the text
+    len: usize,
+    grapheme_widths: Peekable<T>,
+    breaks: Peekable<std::vec::IntoIter<(usize, BreakOpportunity)>>,
+    pending: VecDeque<TokenKind>,
+}
+
+impl<'a, T> UnicodeLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = GraphemeWidth<'a>> + Clone,
+{
+    pub fn new(grapheme_widths: Peekable<T>, text: &'a str) -> Self {
+        let breaks: Vec<(usize, BreakOpportunity)> = linebreaks(text).collect();
+
+        Self {
+            index: 0,
+            len: text.len(),
+            grapheme_widths,
+            breaks: breaks.into_iter().peekable(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Fills `pending` with the tokens up to the next break opportunity.
+    fn next_segment(&mut self) {
+        // Break opportunities that fell inside of a grapheme are skipped
+        let index = self.index;
+        while self.breaks.next_if(|&(end, _)| end <= index).is_some() {}
+        let (segment_end, opportunity) = self
+            .breaks
+            .peek()
+            .copied()
+            .unwrap_or((usize::MAX, BreakOpportunity::Allowed));
+
+        let start = self.index;
+        let mut graphemes = Vec::new();
+
+        while self.index < segment_end {
+            let grapheme_width = match self.grapheme_widths.next() {
+                Some(grapheme_width) => grapheme_width,
+                None => break,
+            };
+
+            self.index += grapheme_width.grapheme.len();
+            graphemes.push(grapheme_width);
+        }
+
+        // The segment ends with an optional newline, preceded by optional whitespace and an
+        // optional soft hyphen
+        let is_newline = opportunity == BreakOpportunity::Mandatory
+            && (segment_end < self.len
+                || graphemes
+                    .last()
+                    .is_some_and(|last| ends_with_hard_break(last.grapheme)));
+        let newline = usize::from(is_newline);
+        let white_space = graphemes[..graphemes.len() - newline]
+            .iter()
+            .rev()
+            .take_while(|g| is_white_space(g.grapheme))
+            .count();
+        let soft_hyphen = graphemes[..graphemes.len() - newline - white_space]
+            .last()
+            .map_or(0, |last| usize::from(last.grapheme == SOFT_HYPHEN));
+        let required = graphemes.len() - newline - white_space - soft_hyphen;
+
+        let parts = [
+            (required, Kind::Required),
+            (soft_hyphen, Kind::SoftHyphen),
+            (white_space, Kind::Optional),
+            (newline, Kind::Newline),
+        ];
+
+        let mut token_start = start;
+        let mut graphemes = &graphemes[..];
+        for &(count, kind) in parts.iter() {
+            let (part, rest) = graphemes.split_at(count);
+            graphemes = rest;
+
+            if part.is_empty() {
+                continue;
+            }
+
+            let token_end = token_start + part.iter().map(|g| g.grapheme.len()).sum::<usize>();
+            self.pending
+                .push_back(kind.token(Token::new(token_start, token_end, width(part))));
+            token_start = token_end;
+        }
+    }
+}
+
+impl<'a, T> Iterator for UnicodeLineBreakIterator<'a, T>
+where
+    T: Iterator<Item = GraphemeWidth<'a>> + Clone,
+{
+    type Item = TokenKind;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            // will return None if there is no more text
+            self.grapheme_widths.peek()?;
+            self.next_segment();
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{grapheme_width::WithGraphemeWidth, measure::TTFParserMeasure, Measure};
+
+    use super::*;
+
+    #[test]
+    fn words() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "at newline\n  some thing";
+        let mut iter = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text);
+
+        let words: Vec<&str> = iter
+            .clone()
+            .map(|t| t.into_token().unwrap().as_str(text))
+            .collect();
+        assert_eq!(
+            words,
+            vec!["at", " ", "newline", "\n", "  ", "some", " ", "thing"]
+        );
+
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Optional(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Newline(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Optional(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Optional(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn hyphens_and_dashes() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "devil-bought and/or a\u{2014}b";
+        let words: Vec<&str> = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text)
+            .map(|t| t.into_token().unwrap().as_str(text))
+            .collect();
+
+        assert_eq!(
+            words,
+            vec!["devil-", "bought", " ", "and/", "or", " ", "a", "\u{2014}", "b"]
+        );
+    }
+
+    #[test]
+    fn ideographs() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "今日は!";
+        let words: Vec<&str> = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text)
+            .map(|t| t.into_token().unwrap().as_str(text))
+            .collect();
+
+        assert_eq!(words, vec!["今", "日", "は!"]);
+    }
+
+    #[test]
+    fn no_break_space() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "10\u{a0}km away";
+        let mut iter = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text);
+
+        let token = iter.next().unwrap();
+        assert!(matches!(token, TokenKind::Required(_)));
+        assert_eq!("10\u{a0}km", token.into_token().unwrap().as_str(text));
+        assert!(matches!(iter.next(), Some(TokenKind::Optional(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn sequential_newlines() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "\r\n\n  \r\n\n";
+        let mut iter = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text);
+
+        let words: Vec<&str> = iter
+            .clone()
+            .map(|t| t.into_token().unwrap().as_str(text))
+            .collect();
+        assert_eq!(words, vec!["\r\n", "\n", "  ", "\r\n", "\n"]);
+
+        assert!(matches!(iter.next(), Some(TokenKind::Newline(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Newline(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Optional(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Newline(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Newline(_))));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn mandatory_breaks() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // A line separator and a next line char at the end of the text are hard breaks
+        let text = "a\u{2028}b\u{85}";
+        let mut iter = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text);
+
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Newline(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Newline(_))));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn widths() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "aoeu-aoeu";
+        let widths: Vec<u32> = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text)
+            .map(|t| t.width())
+            .collect();
+
+        assert_eq!(widths, vec![measure.str("aoeu-"), measure.str("aoeu")]);
+    }
+
+    #[test]
+    fn soft_hyphen() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "soft\u{AD}ware ";
+        let mut iter = text
+            .with_grapheme_width(&measure)
+            .tokenize_unicode_line_break(text);
+
+        let token = iter.next().unwrap();
+        assert_eq!("soft", token.into_token().unwrap().as_str(text));
+
+        let token = iter.next().unwrap();
+        assert!(matches!(token, TokenKind::SoftHyphen(_)));
+        assert_eq!(token.width(), measure.str("-"));
+
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(matches!(iter.next(), Some(TokenKind::Optional(_))));
+        assert!(iter.next().is_none());
+    }
+}
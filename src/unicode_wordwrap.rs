@@ -0,0 +1,232 @@
+use crate::{
+    alignment::Alignment,
+    grapheme_width::{GraphemeWidthIterator, WithGraphemeWidth},
+    hyphenation::{HyphenationIterator, Hyphenator, WithHyphenation},
+    line::{LineIterator, Lines},
+    line_break::{AddNewlines, LineBreakIterator},
+    line_widths::LineWidths,
+    metrics::{Metrics, MetricsIterator},
+    partial_tokens::{PartialTokensIterator, WithPartialTokens},
+    position::{PositionIterator, Positions},
+    unicode_line_break::{TokenizeUnicodeLineBreak, UnicodeLineBreakIterator},
+    wordwrap::{WordWrap, WordWrapWithMetrics, WordWrapWithPosition},
+    Measure, VerticalMetrics,
+};
+
+/// WordWrap for variable-width TTF text, breaking lines where the Unicode Line Breaking Algorithm
+/// ([UAX #14](https://www.unicode.org/reports/tr14/)) allows.
+///
+/// Unlike `WhiteSpaceWordWrap` lines may also break after hyphens, slashes and dashes, and between
+/// ideographs.
+#[derive(Debug)]
+pub struct UnicodeWordWrap<'fnt> {
+    max_width: u32,
+    line_widths: LineWidths,
+    measure: &'fnt dyn Measure,
+    hyphenator: Option<&'fnt Hyphenator>,
+    alignment: Alignment,
+    vertical_metrics: Option<VerticalMetrics>,
+}
+
+impl<'fnt> UnicodeWordWrap<'fnt> {
+    /// Creates a new `UnicodeWordWrap`
+    ///
+    /// Will wrap at `max_width` and measure the glyphs using `font_face`
+    pub fn new(max_width: u32, measure: &'fnt dyn Measure) -> Self {
+        Self {
+            max_width,
+            line_widths: LineWidths::new(max_width),
+            measure,
+            hyphenator: None,
+            alignment: Alignment::Left,
+            vertical_metrics: None,
+        }
+    }
+
+    /// Hyphenates words that are wider than `max_width` with `hyphenator`, before breaking them
+    /// anywhere.
+    pub fn with_hyphenator(mut self, hyphenator: &'fnt Hyphenator) -> Self {
+        self.hyphenator = Some(hyphenator);
+        self
+    }
+
+    /// Wraps each line at the width that `widths` returns for its number, and the lines it
+    /// returns `None` for at `max_width`.
+    ///
+    /// Text can flow around a shape, such as an image floated next to the first lines. `widths` is
+    /// only called for the lines that are filled, so it can describe any number of lines. Words
+    /// wider than `narrowest`, the width of the narrowest line, are hyphenated.
+    pub fn with_line_widths<F>(mut self, narrowest: u32, widths: F) -> Self
+    where
+        F: Fn(u32) -> Option<u32> + 'static,
+    {
+        self.line_widths = self.line_widths.with_widths(narrowest, widths);
+        self
+    }
+
+    /// Indents the first line of each paragraph by `indent`.
+    pub fn with_first_line_indent(mut self, indent: u32) -> Self {
+        self.line_widths = self.line_widths.with_first_line_indent(indent);
+        self
+    }
+
+    /// Indents every line but the first of each paragraph by `indent`.
+    pub fn with_hanging_indent(mut self, indent: u32) -> Self {
+        self.line_widths = self.line_widths.with_hanging_indent(indent);
+        self
+    }
+
+    /// Places the lines of `wrap_with_position()` within their widths using `alignment`.
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Places the lines of `wrap_with_metrics()` vertically using `vertical_metrics`.
+    pub fn with_vertical_metrics(mut self, vertical_metrics: VerticalMetrics) -> Self {
+        self.vertical_metrics = Some(vertical_metrics);
+        self
+    }
+}
+
+type UnicodeLineBreakWrapIterator<'m> = LineBreakIterator<
+    PartialTokensIterator<
+        'm,
+        HyphenationIterator<'m, UnicodeLineBreakIterator<'m, GraphemeWidthIterator<'m>>>,
+    >,
+>;
+
+impl<'m, 'txt: 'm> WordWrap<'m, 'txt> for UnicodeWordWrap<'m> {
+    type Iterator = LineIterator<'txt, UnicodeLineBreakWrapIterator<'m>>;
+
+    fn word_wrap(&self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_unicode_line_break(text)
+            .with_hyphenation(
+                self.line_widths.narrowest(),
+                text,
+                self.measure,
+                self.hyphenator,
+            )
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .with_line_widths(self.line_widths.clone())
+            .lines(text)
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithPosition<'m, 'txt> for UnicodeWordWrap<'m> {
+    type Iterator = PositionIterator<'m, UnicodeLineBreakWrapIterator<'m>>;
+
+    fn word_wrap_with_position(&'m self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_unicode_line_break(text)
+            .with_hyphenation(
+                self.line_widths.narrowest(),
+                text,
+                self.measure,
+                self.hyphenator,
+            )
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .with_line_widths(self.line_widths.clone())
+            .positions(text, self.measure)
+            .aligned(self.alignment, self.line_widths.clone())
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithMetrics<'m, 'txt> for UnicodeWordWrap<'m> {
+    type Iterator = MetricsIterator<UnicodeLineBreakWrapIterator<'m>>;
+
+    fn word_wrap_with_metrics(&'m self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_unicode_line_break(text)
+            .with_hyphenation(
+                self.line_widths.narrowest(),
+                text,
+                self.measure,
+                self.hyphenator,
+            )
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .with_line_widths(self.line_widths.clone())
+            .metrics()
+            .with_vertical_metrics(self.vertical_metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{CharPosition, Position, TTFParserMeasure, Wrap, WrapWithPosition};
+
+    use super::*;
+
+    #[test]
+    fn hyphenated_words() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = UnicodeWordWrap::new(20000, &measure);
+
+        let lines: Vec<&str> = "the soul of the devil-bought hastes not from his charnel clay"
+            .wrap(&word_wrap)
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "the soul of the devil-",
+                "bought hastes not",
+                "from his charnel clay",
+            ]
+        );
+    }
+
+    #[test]
+    fn break_at_hyphen() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = UnicodeWordWrap::new(5000, &measure);
+
+        let lines: Vec<&str> = "well-to-do".wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["well-", "to-do"]);
+    }
+
+    #[test]
+    fn mixed_scripts() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        // Roboto has no width for the ideographs, but there is a break opportunity after them
+        let word_wrap = UnicodeWordWrap::new(6000, &measure);
+
+        let lines: Vec<&str> = "hello今日はworld".wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["hello今日は", "world"]);
+    }
+
+    #[test]
+    fn positions() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let word_wrap = UnicodeWordWrap::new(5000, &measure);
+
+        let positions: Vec<CharPosition> = "well-to-do".wrap_with_position(&word_wrap).collect();
+        assert!(matches!(
+            positions[5],
+            CharPosition::Known(Position {
+                ch: 't',
+                line: 1,
+                offset: 0,
+                ..
+            })
+        ));
+    }
+}
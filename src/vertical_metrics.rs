@@ -0,0 +1,101 @@
+use ttf_parser::Face;
+
+/// The vertical extent of lines of text, in font units.
+///
+/// Lines are laid out from the top of the first line, with `y` increasing downwards.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct VerticalMetrics {
+    /// Distance from the baseline to the top of the line
+    pub ascent: i16,
+
+    /// Distance from the baseline to the bottom of the line, usually negative
+    pub descent: i16,
+
+    /// Extra space between lines
+    pub line_gap: i16,
+
+    /// Multiplies the distance from one baseline to the next
+    line_height: f32,
+}
+
+impl VerticalMetrics {
+    /// Creates new `VerticalMetrics`
+    pub fn new(ascent: i16, descent: i16, line_gap: i16) -> Self {
+        Self {
+            ascent,
+            descent,
+            line_gap,
+            line_height: 1.0,
+        }
+    }
+
+    /// Creates `VerticalMetrics` from the `hhea` table of `font_face`, or the typographic metrics
+    /// of its `OS/2` table when the font asks for them.
+    pub fn from_face(font_face: &Face<'_>) -> Self {
+        Self::new(
+            font_face.ascender(),
+            font_face.descender(),
+            font_face.line_gap(),
+        )
+    }
+
+    /// Multiplies the distance between baselines by `line_height`, 1.5 spaces lines by half
+    /// again.
+    ///
+    /// The extra space is split evenly above and below each line.
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    /// The distance from one baseline to the next.
+    pub fn line_height(&self) -> u32 {
+        let height = i32::from(self.ascent) - i32::from(self.descent) + i32::from(self.line_gap);
+        (height.max(0) as f32 * self.line_height).round() as u32
+    }
+
+    /// The `y` of the top of `line`.
+    pub fn top(&self, line: u32) -> i32 {
+        (line as i32).saturating_mul(self.line_height() as i32)
+    }
+
+    /// The `y` of the baseline of `line`.
+    pub fn baseline(&self, line: u32) -> i32 {
+        let glyph_height = i32::from(self.ascent) - i32::from(self.descent);
+        let half_leading = (self.line_height() as i32 - glyph_height) / 2;
+        self.top(line) + half_leading + i32::from(self.ascent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roboto() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+
+        let metrics = VerticalMetrics::from_face(&font_face);
+        assert_eq!(metrics.ascent, 1900);
+        assert_eq!(metrics.descent, -500);
+        assert_eq!(metrics.line_gap, 0);
+
+        assert_eq!(metrics.line_height(), 2400);
+        assert_eq!(metrics.top(2), 4800);
+        assert_eq!(metrics.baseline(0), 1900);
+        assert_eq!(metrics.baseline(2), 6700);
+    }
+
+    #[test]
+    fn line_height() {
+        let metrics = VerticalMetrics::new(1900, -500, 0).with_line_height(1.5);
+
+        assert_eq!(metrics.line_height(), 3600);
+        assert_eq!(metrics.top(1), 3600);
+
+        // Half of the extra 1200 is above the line
+        assert_eq!(metrics.baseline(0), 600 + 1900);
+        assert_eq!(metrics.baseline(1), 3600 + 600 + 1900);
+    }
+}
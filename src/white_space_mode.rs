@@ -0,0 +1,382 @@
+use std::{collections::VecDeque, fmt::Formatter};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    token::{Token, TokenKind},
+    Measure,
+};
+
+/// How whitespace and newlines are handled, modeled on the CSS `white-space` property.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WhiteSpace {
+    /// Whitespace and newlines collapse into single spaces, and lines wrap.
+    Normal,
+
+    /// Whitespace is kept, newlines break lines, and lines do not wrap.
+    Pre,
+
+    /// Whitespace is kept, newlines break lines, and lines wrap.
+    ///
+    /// The whitespace that a line wraps at is not drawn, as if it hangs past the end of the line.
+    PreWrap,
+
+    /// Whitespace collapses into single spaces, newlines break lines, and lines wrap.
+    PreLine,
+
+    /// Whitespace and newlines collapse into single spaces, and lines do not wrap.
+    NoWrap,
+
+    /// Like `PreWrap`, but whitespace at the end of a line takes up space, and lines can break
+    /// after each whitespace char.
+    BreakSpaces,
+}
+
+impl WhiteSpace {
+    /// Whether runs of whitespace are drawn as a single space
+    pub(crate) fn collapses(self) -> bool {
+        matches!(
+            self,
+            WhiteSpace::Normal | WhiteSpace::PreLine | WhiteSpace::NoWrap
+        )
+    }
+
+    /// Whether newlines in the text break lines
+    pub(crate) fn keeps_newlines(self) -> bool {
+        !matches!(self, WhiteSpace::Normal | WhiteSpace::NoWrap)
+    }
+
+    /// Whether lines are wrapped at the max_width
+    pub(crate) fn wraps(self) -> bool {
+        !matches!(self, WhiteSpace::Pre | WhiteSpace::NoWrap)
+    }
+
+    /// Whether whitespace at the start of a paragraph is drawn
+    pub(crate) fn keeps_leading_white_space(self) -> bool {
+        !self.collapses()
+    }
+
+    /// Whether whitespace at the end of the text is drawn, instead of hanging past the end of
+    /// the last line
+    pub(crate) fn keeps_trailing_white_space(self) -> bool {
+        !self.collapses() && !self.wraps()
+    }
+}
+
+pub trait WithWhiteSpace<T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn with_white_space<'a>(
+        self,
+        white_space: Option<WhiteSpace>,
+        text: &'a str,
+        measure: &'a dyn Measure,
+    ) -> WhiteSpaceModeIterator<'a, T>;
+}
+
+impl<T> WithWhiteSpace<T> for T
+where
+    T: Iterator<Item = TokenKind>,
+{
+    fn with_white_space<'a>(
+        self,
+        white_space: Option<WhiteSpace>,
+        text: &'a str,
+        measure: &'a dyn Measure,
+    ) -> WhiteSpaceModeIterator<'a, T> {
+        WhiteSpaceModeIterator {
+            white_space,
+            text,
+            measure,
+            tokens: self,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+/// Collapses or splits the whitespace tokens of the text for a `WhiteSpace` mode
+///
+/// Without a mode the tokens pass through.
+#[derive(Clone)]
+pub struct WhiteSpaceModeIterator<'a, T> {
+    white_space: Option<WhiteSpace>,
+    text: &'a str,
+    measure: &'a dyn Measure,
+    tokens: T,
+
+    /// Tokens that were read ahead, or split off of a token
+    pending: VecDeque<TokenKind>,
+}
+
+impl<'a, T> std::fmt::Debug for WhiteSpaceModeIterator<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WhiteSpaceModeIterator")
+            .field("white_space", &self.white_space)
+            .finish()
+    }
+}
+
+impl<'a, T> WhiteSpaceModeIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    /// Whitespace, with the newlines that do not break lines
+    fn white_space_token(&self, token_kind: TokenKind, white_space: WhiteSpace) -> Option<Token> {
+        match token_kind {
+            TokenKind::Optional(token) => Some(token),
+            TokenKind::Newline(token) if !white_space.keeps_newlines() => token,
+            _ => None,
+        }
+    }
+
+    /// Merges the run of whitespace that starts with `token` into one space.
+    fn collapse(&mut self, mut token: Token, white_space: WhiteSpace) -> Option<TokenKind> {
+        while let Some(token_kind) = self.tokens.next() {
+            if let Some(next) = self.white_space_token(token_kind, white_space) {
+                token.end = next.end;
+                continue;
+            }
+
+            if token_kind.is_newline() {
+                // Whitespace before a newline is removed
+                return Some(token_kind);
+            }
+
+            self.pending.push_back(token_kind);
+            break;
+        }
+
+        token.display_width = self.measure.char_at(token.start, ' ').map_or(0, u32::from);
+        Some(TokenKind::Optional(token))
+    }
+
+    /// Splits the first whitespace grapheme off of `token`, it takes up space like a word and the
+    /// line can break after it.
+    fn break_spaces(&mut self, token: Token) -> Option<TokenKind> {
+        if token.start == token.end {
+            // The line break opportunity after a grapheme
+            return Some(TokenKind::Optional(token));
+        }
+
+        let grapheme = self.text[token.start..token.end]
+            .graphemes(true)
+            .next()
+            .unwrap_or_default();
+
+        let head = Token::new(
+            token.start,
+            token.start + grapheme.len(),
+            self.measure.str_at(token.start, grapheme),
+        );
+        self.pending
+            .push_back(TokenKind::Optional(Token::new(head.end, head.end, 0)));
+        if head.end < token.end {
+            let tail = Token::new(
+                head.end,
+                token.end,
+                token.display_width.saturating_sub(head.display_width),
+            );
+            self.pending.push_back(TokenKind::Optional(tail));
+        }
+
+        Some(TokenKind::Required(head))
+    }
+}
+
+impl<'a, T> Iterator for WhiteSpaceModeIterator<'a, T>
+where
+    T: Iterator<Item = TokenKind>,
+{
+    type Item = TokenKind;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token_kind = self.pending.pop_front().or_else(|| self.tokens.next())?;
+
+        let white_space = match self.white_space {
+            Some(white_space) => white_space,
+            None => return Some(token_kind),
+        };
+
+        match self.white_space_token(token_kind, white_space) {
+            Some(token) if white_space.collapses() => self.collapse(token, white_space),
+            Some(token) if white_space == WhiteSpace::BreakSpaces => self.break_spaces(token),
+            _ => Some(token_kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ttf_parser::Face;
+
+    use crate::{
+        CharPosition, TTFParserMeasure, WhiteSpaceWordWrap, Wrap, WrapWithMetrics, WrapWithPosition,
+    };
+
+    use super::*;
+
+    const TEXT: &str = "  Two  words\n  on\tlines  ";
+
+    /// The text of each line of the metrics
+    fn lines<'a>(text: &'a str, word_wrap: &WhiteSpaceWordWrap<'_>) -> Vec<&'a str> {
+        text.wrap_with_metrics(word_wrap)
+            .map(|line| &text[line.range])
+            .collect()
+    }
+
+    /// The chars drawn on each line
+    fn drawn(text: &str, word_wrap: &WhiteSpaceWordWrap<'_>) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for position in text.wrap_with_position(word_wrap) {
+            if let CharPosition::Known(position) = position {
+                let line = position.line as usize;
+                lines.resize(lines.len().max(line + 1), String::new());
+                lines[line].push(position.ch);
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn default() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("  Two  wo"), &measure);
+
+        // Whitespace is kept within lines, the tab has no glyph
+        let lines: Vec<&str> = TEXT.wrap(&word_wrap).collect();
+        assert_eq!(lines, vec!["Two", "words", "on\tlines"]);
+        assert_eq!(drawn(TEXT, &word_wrap), vec!["Two", "words", "onlines"]);
+    }
+
+    #[test]
+    fn normal() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("  Two  wo"), &measure)
+            .with_white_space(WhiteSpace::Normal);
+
+        // The newline collapses into a space, so "on" fits after "words"
+        assert_eq!(lines(TEXT, &word_wrap), vec!["Two", "words\n  on", "lines"]);
+        assert_eq!(drawn(TEXT, &word_wrap), vec!["Two", "words on", "lines"]);
+
+        // The lines of wrap() break at the same places, but are not collapsed
+        let wrapped: Vec<&str> = TEXT.wrap(&word_wrap).collect();
+        assert_eq!(wrapped, vec!["Two", "words\n  on", "lines"]);
+
+        let space = TEXT
+            .wrap_with_position(&word_wrap)
+            .find(|position| position.index() == TEXT.find('\n').unwrap());
+        match space {
+            Some(CharPosition::Known(position)) => {
+                assert_eq!(position.ch, ' ');
+                assert_eq!(position.line, 1);
+                assert_eq!(position.offset, measure.str("words"));
+            }
+            _ => panic!("The newline should be drawn as a space"),
+        }
+    }
+
+    #[test]
+    fn pre() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("  Two  wo"), &measure)
+            .with_white_space(WhiteSpace::Pre);
+
+        // Lines only break at the newline, and keep their leading whitespace
+        assert_eq!(
+            lines(TEXT, &word_wrap),
+            vec!["  Two  words", "  on\tlines  "]
+        );
+        assert_eq!(drawn(TEXT, &word_wrap), vec!["  Two  words", "  onlines  "]);
+
+        let wrapped: Vec<&str> = TEXT.wrap(&word_wrap).collect();
+        assert_eq!(wrapped, vec!["  Two  words", "  on\tlines  "]);
+    }
+
+    #[test]
+    fn pre_wrap() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("  Two  wo"), &measure)
+            .with_white_space(WhiteSpace::PreWrap);
+
+        // Paragraphs keep their leading whitespace, the whitespace lines wrap at is not drawn
+        assert_eq!(
+            lines(TEXT, &word_wrap),
+            vec!["  Two", "words", "  on\tlines"]
+        );
+        assert_eq!(drawn(TEXT, &word_wrap), vec!["  Two", "words", "  onlines"]);
+
+        let wrapped: Vec<&str> = TEXT.wrap(&word_wrap).collect();
+        assert_eq!(wrapped, vec!["  Two", "words", "  on\tlines"]);
+    }
+
+    #[test]
+    fn pre_line() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("  Two  wo"), &measure)
+            .with_white_space(WhiteSpace::PreLine);
+
+        // Lines break at the newline, the tab collapses into a space
+        assert_eq!(lines(TEXT, &word_wrap), vec!["Two", "words", "on\tlines"]);
+        assert_eq!(drawn(TEXT, &word_wrap), vec!["Two", "words", "on lines"]);
+
+        let wrapped: Vec<&str> = TEXT.wrap(&word_wrap).collect();
+        assert_eq!(wrapped, vec!["Two", "words", "on\tlines"]);
+    }
+
+    #[test]
+    fn no_wrap() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("  Two  wo"), &measure)
+            .with_white_space(WhiteSpace::NoWrap);
+
+        // Everything is on one line, with single spaces between the words
+        assert_eq!(lines(TEXT, &word_wrap), vec!["Two  words\n  on\tlines"]);
+        assert_eq!(drawn(TEXT, &word_wrap), vec!["Two words on lines"]);
+
+        let wrapped: Vec<&str> = TEXT.wrap(&word_wrap).collect();
+        assert_eq!(wrapped, vec!["Two  words\n  on\tlines"]);
+    }
+
+    #[test]
+    fn break_spaces() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("  Two  wo"), &measure)
+            .with_white_space(WhiteSpace::BreakSpaces);
+
+        // Whitespace is drawn at the ends of lines
+        assert_eq!(
+            lines(TEXT, &word_wrap),
+            vec!["  Two  ", "words", "  on\tlines  "]
+        );
+        assert_eq!(
+            drawn(TEXT, &word_wrap),
+            vec!["  Two  ", "words", "  onlines  "]
+        );
+
+        let wrapped: Vec<&str> = TEXT.wrap(&word_wrap).collect();
+        assert_eq!(wrapped, vec!["  Two  ", "words", "  on\tlines  "]);
+
+        // And wraps when it does not fit
+        let word_wrap = WhiteSpaceWordWrap::new(measure.str("lines "), &measure)
+            .with_white_space(WhiteSpace::BreakSpaces);
+        assert_eq!(lines("lines  ", &word_wrap), vec!["lines ", " "]);
+
+        let wrapped: Vec<&str> = "lines  ".wrap(&word_wrap).collect();
+        assert_eq!(wrapped, vec!["lines ", " "]);
+    }
+}
@@ -1,11 +1,16 @@
 use std::iter::Peekable;
 
-use crate::{grapheme_width::GraphemeWidth, measure::Measure, token::Token, token::TokenKind};
+use crate::{
+    grapheme_width::GraphemeWidth,
+    measure::Measure,
+    token::{Token, TokenKind, SOFT_HYPHEN},
+};
 
 #[derive(Copy, Clone, PartialEq)]
 enum State {
     Newline,
     WhiteSpace,
+    SoftHyphen,
     Other,
 }
 
@@ -13,6 +18,7 @@ impl From<&str> for State {
     fn from(s: &str) -> Self {
         match s {
             "\r\n" | "\n" => State::Newline,
+            SOFT_HYPHEN => State::SoftHyphen,
             s => {
                 if let Some(first) = s.chars().next() {
                     if first.is_whitespace() {
@@ -49,6 +55,7 @@ where
 {
     index: usize,
     grapheme_widths: Peekable<T>,
+    #[allow(dead_code)]
     measure: &'a dyn Measure,
 }
 
@@ -78,8 +85,8 @@ where
         let state = State::from(grapheme_width.grapheme);
 
         // keep track of the start of the span
-        let start = self.index as usize;
-        let mut end = self.index as usize;
+        let start = self.index;
+        let mut end = self.index;
 
         let mut widths = Vec::new();
 
@@ -94,13 +101,18 @@ where
             // The char is the same 'mode', advance the iterator
             let char_width = self.grapheme_widths.next().unwrap();
 
+            let kerning = if end == start { 0 } else { char_width.kerning };
+
             // Increment the end of the span
             end += char_width.grapheme.len();
-            total_width += u32::from(char_width.display_width);
+            total_width += char_width.display_width;
+
+            // Kerning only applies between the graphemes of the token
+            total_width = total_width.saturating_add_signed(i32::from(kerning));
             widths.push(char_width.display_width);
 
-            // Do not group newlinesn together, break
-            if state == State::Newline {
+            // Do not group newlinesn or soft hyphens together, break
+            if state == State::Newline || state == State::SoftHyphen {
                 break;
             }
         }
@@ -116,6 +128,7 @@ where
         match state {
             State::Newline => TokenKind::Newline(Some(token)),
             State::WhiteSpace => TokenKind::Optional(token),
+            State::SoftHyphen => TokenKind::SoftHyphen(token),
             State::Other => TokenKind::Required(token),
         }
         .into()
@@ -127,7 +140,7 @@ mod tests {
 
     use ttf_parser::Face;
 
-    use crate::{grapheme_width::WithGraphemeWidth, measure::TTFParserMeasure};
+    use crate::{grapheme_width::WithGraphemeWidth, measure::TTFParserMeasure, Measure};
 
     use super::*;
 
@@ -248,6 +261,23 @@ mod tests {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn kerning() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "To Te";
+        let iter = WhiteSpaceIterator::new(text.with_grapheme_width(&measure).peekable(), &measure);
+
+        let widths: Vec<u32> = iter.map(|t| t.width()).collect();
+        assert_eq!(
+            widths,
+            vec![measure.str("To"), measure.str(" "), measure.str("Te")]
+        );
+        assert!(widths[0] < measure.str("T") + measure.str("o"));
+    }
+
     #[test]
     fn test_y() {
         let font_data = crate::tests::read_font();
@@ -269,4 +299,25 @@ mod tests {
         assert!(matches!(token, Some(TokenKind::Required(_))));
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn soft_hyphen() {
+        let font_data = crate::tests::read_font();
+        let font_face = Face::parse(&font_data, 0).expect("TTF should be valid");
+        let measure = TTFParserMeasure::new(&font_face);
+
+        let text = "soft\u{AD}ware";
+        let mut iter =
+            WhiteSpaceIterator::new(text.with_grapheme_width(&measure).peekable(), &measure);
+
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+
+        // The soft hyphen is as wide as a hyphen
+        let token = iter.next().unwrap();
+        assert!(matches!(token, TokenKind::SoftHyphen(_)));
+        assert_eq!(token.width(), measure.str("-"));
+
+        assert!(matches!(iter.next(), Some(TokenKind::Required(_))));
+        assert!(iter.next().is_none());
+    }
 }
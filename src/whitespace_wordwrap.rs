@@ -1,19 +1,30 @@
 use crate::{
+    alignment::Alignment,
     grapheme_width::{GraphemeWidthIterator, WithGraphemeWidth},
+    hit_test::Layout,
+    hyphenation::{HyphenationIterator, Hyphenator, WithHyphenation},
     line::{LineIterator, Lines},
     line_break::{AddNewlines, LineBreakIterator},
+    line_widths::LineWidths,
+    metrics::{Metrics, MetricsIterator},
     partial_tokens::{PartialTokensIterator, WithPartialTokens},
     position::{PositionIterator, Positions},
+    white_space_mode::{WhiteSpaceModeIterator, WithWhiteSpace},
     whitespace::{TokenizeWhiteSpace, WhiteSpaceIterator},
-    wordwrap::{WordWrap, WordWrapWithPosition},
-    Measure,
+    wordwrap::{WordWrap, WordWrapWithMetrics, WordWrapWithPosition},
+    Measure, VerticalMetrics, WhiteSpace, WrapWithMetrics, WrapWithPosition,
 };
 
 /// WordWrap for variable-width TTF text.
 #[derive(Debug)]
 pub struct WhiteSpaceWordWrap<'fnt> {
     max_width: u32,
+    line_widths: LineWidths,
     measure: &'fnt dyn Measure,
+    hyphenator: Option<&'fnt Hyphenator>,
+    alignment: Alignment,
+    vertical_metrics: Option<VerticalMetrics>,
+    white_space: Option<WhiteSpace>,
 }
 
 impl<'fnt> WhiteSpaceWordWrap<'fnt> {
@@ -21,40 +32,158 @@ impl<'fnt> WhiteSpaceWordWrap<'fnt> {
     ///
     /// Will wrap at `max_width` and measure the glyphs using `font_face`
     pub fn new(max_width: u32, measure: &'fnt dyn Measure) -> Self {
-        Self { max_width, measure }
+        Self {
+            max_width,
+            line_widths: LineWidths::new(max_width),
+            measure,
+            hyphenator: None,
+            alignment: Alignment::Left,
+            vertical_metrics: None,
+            white_space: None,
+        }
+    }
+
+    /// Hyphenates words that are wider than `max_width` with `hyphenator`, before breaking them
+    /// anywhere.
+    pub fn with_hyphenator(mut self, hyphenator: &'fnt Hyphenator) -> Self {
+        self.hyphenator = Some(hyphenator);
+        self
+    }
+
+    /// Wraps each line at the width that `widths` returns for its number, and the lines it
+    /// returns `None` for at `max_width`.
+    ///
+    /// Text can flow around a shape, such as an image floated next to the first lines. `widths` is
+    /// only called for the lines that are filled, so it can describe any number of lines. Words
+    /// wider than `narrowest`, the width of the narrowest line, are hyphenated.
+    pub fn with_line_widths<F>(mut self, narrowest: u32, widths: F) -> Self
+    where
+        F: Fn(u32) -> Option<u32> + 'static,
+    {
+        self.line_widths = self.line_widths.with_widths(narrowest, widths);
+        self
+    }
+
+    /// Indents the first line of each paragraph by `indent`.
+    pub fn with_first_line_indent(mut self, indent: u32) -> Self {
+        self.line_widths = self.line_widths.with_first_line_indent(indent);
+        self
+    }
+
+    /// Indents every line but the first of each paragraph by `indent`.
+    pub fn with_hanging_indent(mut self, indent: u32) -> Self {
+        self.line_widths = self.line_widths.with_hanging_indent(indent);
+        self
+    }
+
+    /// Places the lines of `wrap_with_position()` within their widths using `alignment`.
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Places the lines of `wrap_with_metrics()` vertically using `vertical_metrics`.
+    pub fn with_vertical_metrics(mut self, vertical_metrics: VerticalMetrics) -> Self {
+        self.vertical_metrics = Some(vertical_metrics);
+        self
+    }
+
+    /// Collapses whitespace, breaks lines at newlines and wraps them as the CSS `white-space`
+    /// property does.
+    ///
+    /// Without a mode, whitespace is kept within lines and dropped where they wrap or start.
+    /// The lines of `wrap()` break where the positions and metrics do, but they are slices of the
+    /// text, so their whitespace is not collapsed.
+    pub fn with_white_space(mut self, white_space: WhiteSpace) -> Self {
+        self.white_space = Some(white_space);
+        self
+    }
+
+    /// Lays out `text` as `wrap_with_position()` and `wrap_with_metrics()` do, to hit test points,
+    /// place carets and select ranges in.
+    pub fn layout<'a>(&'a self, text: &'a str) -> Layout<'a> {
+        Layout::new(
+            text,
+            text.wrap_with_position(self).graphemes(),
+            text.wrap_with_metrics(self),
+            &self.line_widths,
+            self.alignment,
+        )
     }
 }
 
-impl<'m, 'txt: 'm> WordWrap<'m, 'txt> for WhiteSpaceWordWrap<'m> {
-    type Iterator = LineIterator<
-        'txt,
-        LineBreakIterator<
-            PartialTokensIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>,
+type WhiteSpaceLineBreakIterator<'m> = LineBreakIterator<
+    PartialTokensIterator<
+        'm,
+        HyphenationIterator<
+            'm,
+            WhiteSpaceModeIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>,
         >,
-    >;
+    >,
+>;
+
+impl<'m, 'txt: 'm> WordWrap<'m, 'txt> for WhiteSpaceWordWrap<'m> {
+    type Iterator = LineIterator<'txt, WhiteSpaceLineBreakIterator<'m>>;
 
     fn word_wrap(&self, text: &'txt str) -> Self::Iterator {
         text.with_grapheme_width(self.measure)
             .tokenize_white_space(self.measure)
+            .with_white_space(self.white_space, text, self.measure)
+            .with_hyphenation(
+                self.line_widths.narrowest(),
+                text,
+                self.measure,
+                self.hyphenator,
+            )
             .with_partial_tokens(self.max_width, text, self.measure)
             .add_newlines_at(self.max_width)
+            .with_line_widths(self.line_widths.clone())
+            .with_white_space(self.white_space)
             .lines(text)
     }
 }
 
 impl<'m, 'txt: 'm> WordWrapWithPosition<'m, 'txt> for WhiteSpaceWordWrap<'m> {
-    type Iterator = PositionIterator<
-        'm,
-        LineBreakIterator<
-            PartialTokensIterator<'m, WhiteSpaceIterator<'m, GraphemeWidthIterator<'m>>>,
-        >,
-    >;
+    type Iterator = PositionIterator<'m, WhiteSpaceLineBreakIterator<'m>>;
 
     fn word_wrap_with_position(&'m self, text: &'txt str) -> Self::Iterator {
         text.with_grapheme_width(self.measure)
             .tokenize_white_space(self.measure)
+            .with_white_space(self.white_space, text, self.measure)
+            .with_hyphenation(
+                self.line_widths.narrowest(),
+                text,
+                self.measure,
+                self.hyphenator,
+            )
             .with_partial_tokens(self.max_width, text, self.measure)
             .add_newlines_at(self.max_width)
+            .with_line_widths(self.line_widths.clone())
+            .with_white_space(self.white_space)
             .positions(text, self.measure)
+            .with_white_space(self.white_space)
+            .aligned(self.alignment, self.line_widths.clone())
+    }
+}
+
+impl<'m, 'txt: 'm> WordWrapWithMetrics<'m, 'txt> for WhiteSpaceWordWrap<'m> {
+    type Iterator = MetricsIterator<WhiteSpaceLineBreakIterator<'m>>;
+
+    fn word_wrap_with_metrics(&'m self, text: &'txt str) -> Self::Iterator {
+        text.with_grapheme_width(self.measure)
+            .tokenize_white_space(self.measure)
+            .with_white_space(self.white_space, text, self.measure)
+            .with_hyphenation(
+                self.line_widths.narrowest(),
+                text,
+                self.measure,
+                self.hyphenator,
+            )
+            .with_partial_tokens(self.max_width, text, self.measure)
+            .add_newlines_at(self.max_width)
+            .with_line_widths(self.line_widths.clone())
+            .with_white_space(self.white_space)
+            .metrics()
+            .with_vertical_metrics(self.vertical_metrics)
     }
 }
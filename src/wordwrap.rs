@@ -51,3 +51,30 @@ where
         word_wrap.word_wrap_with_position(self)
     }
 }
+
+pub trait WordWrapWithMetrics<'fnt, 'txt: 'fnt> {
+    type Iterator: 'fnt;
+    fn word_wrap_with_metrics(&'fnt self, text: &'txt str) -> Self::Iterator;
+}
+
+/// Provides `.wrap_with_metrics()` on `&str`s
+///
+/// The behavior of the wrapping can change depending on the `WordWrap` type passed in.
+pub trait WrapWithMetrics<'fnt, 'txt: 'fnt, T>
+where
+    T: WordWrapWithMetrics<'fnt, 'txt>,
+{
+    /// Based on the `word_wrap` provided, provides an iterator of `LineMetrics` for each line.
+    fn wrap_with_metrics(&self, word_wrap: &'fnt T) -> T::Iterator;
+}
+
+impl<'fnt, 'txt: 'fnt, T> WrapWithMetrics<'fnt, 'txt, T> for &str
+where
+    T: WordWrapWithMetrics<'fnt, 'txt>,
+    T::Iterator: 'fnt,
+    Self: 'txt,
+{
+    fn wrap_with_metrics(&self, word_wrap: &'fnt T) -> T::Iterator {
+        word_wrap.word_wrap_with_metrics(self)
+    }
+}